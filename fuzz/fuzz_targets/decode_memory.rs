@@ -0,0 +1,74 @@
+//! Decodes arbitrary bytes with every memory based loader.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stb::image::*;
+
+/// Keep decoded images well below libFuzzer's default RSS limit
+const LIMITS: Limits = Limits {
+    max_width: None,
+    max_height: None,
+    max_pixels: Some(4096 * 4096),
+    max_alloc_bytes: Some(256 * 1024 * 1024),
+};
+
+const MAX_GIF_PIXELS: u64 = 256 * 256;
+
+const CHANNELS: [Channels; 5] = [
+    Channels::Default,
+    Channels::Grey,
+    Channels::GreyAlpha,
+    Channels::Rgb,
+    Channels::RgbAlpha,
+];
+
+fuzz_target!(|data: &[u8]| {
+    let (selector, data) = match data.split_first() {
+        Some((selector, data)) => (*selector as usize, data),
+        None => return,
+    };
+    let channels = CHANNELS[selector % CHANNELS.len()];
+
+    let info = match stbi_info_from_memory(data) {
+        Ok(info) => info,
+        Err(_) => return,
+    };
+    if LIMITS.check(&info, channels, 4).is_err() {
+        return;
+    }
+
+    if let Ok((info, image)) = stbi_load_from_memory(data, channels) {
+        assert_eq!(image.as_slice().len(), info.size(channels));
+
+        let image = image.into_image();
+        assert_eq!(image.as_slice().len(), info.size(channels));
+    }
+
+    if let Ok((info, image)) = stbi_load_16_from_memory(data, channels) {
+        assert_eq!(image.as_slice().len(), info.size(channels));
+    }
+
+    if let Ok((info, image)) = stbi_loadf_from_memory(data, channels) {
+        assert_eq!(image.as_slice().len(), info.size(channels));
+    }
+
+    let mut buffer = vec![0_u8; info.size(channels)];
+    let _ = stbi_load_from_memory_copy_into(data, channels, &mut buffer);
+
+    let options = LoadOptions::new()
+        .desired_channels(channels)
+        .bit_depth(BitDepth::Auto)
+        .flip_vertically(selector & 0x80 != 0)
+        .limits(LIMITS);
+    let _ = options.load_from_memory(data);
+
+    // Every GIF frame is a full canvas, so a few bytes per frame can add up quickly
+    if info.width as u64 * info.height as u64 > MAX_GIF_PIXELS {
+        return;
+    }
+    if let Ok(animation) = stbi_load_gif_from_memory(data, channels) {
+        assert_eq!(animation.frames().count(), animation.frame_count());
+        assert_eq!(animation.delays().len(), animation.frame_count());
+    }
+});
@@ -0,0 +1,101 @@
+//! Decodes arbitrary bytes through the reader and stream loaders, using a reader that
+//! injects IO failures, short reads and interruptions.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stb::image::*;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+const LIMITS: Limits = Limits {
+    max_width: None,
+    max_height: None,
+    max_pixels: Some(4096 * 4096),
+    max_alloc_bytes: Some(256 * 1024 * 1024),
+};
+
+/// Reader failing once `fail_at` bytes have been consumed
+struct FaultyReader<'a> {
+    inner: Cursor<&'a [u8]>,
+    fail_at: u64,
+    short_reads: bool,
+    interrupt: bool,
+    interrupted: bool,
+}
+
+impl<'a> FaultyReader<'a> {
+    fn new(config: &[u8], data: &'a [u8]) -> Self {
+        FaultyReader {
+            inner: Cursor::new(data),
+            fail_at: u16::from_le_bytes([config[0], config[1]]) as u64,
+            short_reads: config[2] & 1 != 0,
+            interrupt: config[2] & 2 != 0,
+            interrupted: false,
+        }
+    }
+}
+
+impl Read for FaultyReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.interrupt && !self.interrupted {
+            self.interrupted = true;
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        self.interrupted = false;
+
+        let pos = self.inner.position();
+        if pos >= self.fail_at {
+            return Err(io::Error::other("injected failure"));
+        }
+
+        let mut len = buf.len().min((self.fail_at - pos) as usize);
+        if self.short_reads {
+            len = len.min(1 + pos as usize % 7);
+        }
+
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+impl Seek for FaultyReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 3 {
+        return;
+    }
+    let (config, data) = data.split_at(3);
+
+    match stbi_info_from_memory(data) {
+        Ok(info) if LIMITS.check(&info, Channels::RgbAlpha, 4).is_ok() => {}
+        _ => return,
+    }
+
+    let mut reader = FaultyReader::new(config, data);
+    let _ = stbi_info_from_reader(&mut reader);
+
+    let mut reader = FaultyReader::new(config, data);
+    let _ = probe_from_reader(&mut reader);
+
+    let mut reader = FaultyReader::new(config, data);
+    let _ = detect_format_from_reader(&mut reader);
+
+    let mut reader = FaultyReader::new(config, data);
+    if let Ok((info, image)) = stbi_load_from_reader(&mut reader, Channels::RgbAlpha) {
+        assert_eq!(image.as_slice().len(), info.size(Channels::RgbAlpha));
+    }
+
+    let mut reader = FaultyReader::new(config, data);
+    if let Ok((info, image)) = stbi_load_16_from_stream(&mut reader, Channels::Default) {
+        assert_eq!(image.as_slice().len(), info.size(Channels::Default));
+    }
+
+    let mut reader = FaultyReader::new(config, data);
+    let _ = LoadOptions::new()
+        .bit_depth(BitDepth::Auto)
+        .limits(LIMITS)
+        .load_from_reader(&mut reader);
+});
@@ -0,0 +1,46 @@
+//! Probes arbitrary bytes with every header parsing entry point.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stb::image::*;
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let info = stbi_info_from_memory(data);
+    let format = detect_format(data);
+    let is_16_bit = stbi_is_16_bit_from_memory(data).unwrap();
+    let is_hdr = stbi_is_hdr_from_memory(data).unwrap();
+
+    if let Some(format) = format {
+        assert!(format.is_enabled());
+    }
+
+    let mut reader = Cursor::new(data);
+    let reader_info = stbi_info_from_reader(&mut reader);
+    assert_eq!(info.is_ok(), reader_info.is_ok());
+
+    let mut reader = Cursor::new(data);
+    assert_eq!(detect_format_from_reader(&mut reader).unwrap(), format);
+    assert_eq!(reader.position(), 0);
+
+    let mut reader = Cursor::new(data);
+    assert_eq!(stbi_is_16_bit_from_reader(&mut reader).unwrap(), is_16_bit);
+
+    let mut reader = Cursor::new(data);
+    assert_eq!(stbi_is_hdr_from_reader(&mut reader).unwrap(), is_hdr);
+
+    let mut reader = Cursor::new(data);
+    let _ = stbi_info_from_stream(&mut reader);
+
+    if let Ok(probe) = probe(data) {
+        assert_eq!(probe.is_16_bit, is_16_bit);
+        assert_eq!(probe.is_hdr, is_hdr);
+        assert_eq!(probe.format, format);
+
+        let mut reader = Cursor::new(data);
+        let from_reader = probe_from_reader(&mut reader).unwrap();
+        assert_eq!(from_reader.format, format);
+        assert_eq!(reader.position(), 0);
+    }
+});
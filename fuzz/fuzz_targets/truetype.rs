@@ -0,0 +1,65 @@
+//! Parses arbitrary bytes as a font and queries metrics and bitmaps of the first glyphs.
+//!
+//! stb_truetype does no range checking of offsets found in the font file. `FontInfo::new` checks
+//! everything stb reads before handing the data to stb, this target exercises that validation
+//! together with glyph index and bitmap size checks. It never calls `FontInfo::new_unchecked`,
+//! which skips the checks and is expected to crash on malformed fonts.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stb::truetype::*;
+
+/// Number of glyphs to query, starting from index 0
+const MAX_GLYPHS: i32 = 64;
+
+/// Skip bitmaps larger than this, hostile fonts can declare huge glyph boxes
+const MAX_BITMAP_PIXELS: i64 = 512 * 512;
+
+fn bitmap_fits(bbox: BoundingBox) -> bool {
+    let width = bbox.width() as i64;
+    let height = bbox.height() as i64;
+    width >= 0 && height >= 0 && width * height <= MAX_BITMAP_PIXELS
+}
+
+fuzz_target!(|data: &[u8]| {
+    let count = stbtt_get_number_of_fonts(data).unwrap_or(0);
+
+    for index in 0..count.min(4) {
+        let font = match FontInfo::new(data, index) {
+            Some(font) => font,
+            None => continue,
+        };
+
+        let scale = font.scale_for_pixel_height(16.0);
+        let _ = font.v_metrics();
+        let _ = font.bounding_box();
+        let _ = font.codepoint_h_metrics('A');
+        let _ = font.codepoint_kern_advance('A', 'V');
+        let _ = font.find_glyph_index('A');
+
+        assert!(font.glyph_h_metrics(font.num_glyphs()).is_none());
+
+        for glyph in 0..font.num_glyphs().min(MAX_GLYPHS) {
+            let _ = font.glyph_h_metrics(glyph);
+            let _ = font.glyph_kern_advance(glyph, 0);
+            let _ = font.glyph_box(glyph);
+            let _ = font.is_glyph_empty(glyph);
+
+            if !scale.is_finite() || scale <= 0.0 || scale > 16.0 {
+                continue;
+            }
+
+            match font.glyph_bitmap_box(glyph, scale, scale) {
+                Some(bbox) if bitmap_fits(bbox) => {}
+                _ => continue,
+            }
+
+            if let Some(bitmap) = font.glyph_bitmap(glyph, scale, scale) {
+                assert_eq!(bitmap.data.len(), bitmap.size());
+            }
+
+            let _ = font.glyph_sdf(glyph, scale, 2, 128, 32.0);
+        }
+    }
+});
@@ -0,0 +1,91 @@
+//! Encodes arbitrary pixels with every writer and decodes the result back.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stb::image::*;
+use stb::image_write::*;
+
+fn decode(encoded: &[u8], w: i32, h: i32) -> Vec<u8> {
+    let (info, data) =
+        stbi_load_from_memory(encoded, Channels::Default).expect("Failed to decode written image");
+    assert_eq!((info.width, info.height), (w, h));
+    data.into_vec()
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 4 {
+        return;
+    }
+    let (header, pixels) = data.split_at(4);
+
+    let w = 1 + header[0] as i32 % 64;
+    let h = 1 + header[1] as i32 % 64;
+    let comp = 1 + header[2] as i32 % 4;
+    let quality = 1 + header[3] as i32 % 100;
+
+    let size = (w * h * comp) as usize;
+    if pixels.len() < size {
+        return;
+    }
+    let pixels = &pixels[..size];
+
+    let image = ImageView::new(w, h, comp, pixels).unwrap();
+
+    let png = encode_png_to_vec(image).expect("Failed to write PNG");
+    assert_eq!(decode(&png, w, h), pixels);
+
+    let mut func_png = Vec::new();
+    stbi_write_png_to_func(
+        &mut |data| func_png.extend_from_slice(data),
+        w,
+        h,
+        comp,
+        pixels,
+        0,
+    )
+    .expect("Failed to write PNG to func");
+    assert_eq!(func_png, png);
+
+    // Padded rows must encode to the same image
+    let row = (w * comp) as usize;
+    let padded: Vec<u8> = pixels
+        .chunks_exact(row)
+        .flat_map(|row| row.iter().copied().chain([0xAA; 3].iter().copied()))
+        .collect();
+    let padded = ImageView::with_stride(w, h, comp, row + 3, &padded).unwrap();
+    assert_eq!(encode_png_to_vec(padded).unwrap(), png);
+
+    let tga = encode_tga_to_vec(image).expect("Failed to write TGA");
+    let decoded = decode(&tga, w, h);
+    // Grey + alpha is written as 16 bits per pixel, which stb_image reads as RGB555
+    if comp != 2 {
+        assert_eq!(decoded, pixels);
+    }
+    assert_eq!(encode_tga_to_vec(padded).unwrap(), tga);
+
+    // Every encoder setting must produce an image decoding to the same pixels
+    let options = EncoderOptions::new()
+        .png_compression_level(header[3] as i32 % 10)
+        .png_filter(Some(PngFilter::Paeth))
+        .tga_rle(header[3] & 0x80 == 0);
+    let mut png = Vec::new();
+    options.write_png_to_writer(&mut png, image).unwrap();
+    assert_eq!(decode(&png, w, h), pixels);
+
+    let mut tga = Vec::new();
+    options.write_tga_to_writer(&mut tga, image).unwrap();
+    assert_eq!(decode(&tga, w, h), decoded);
+
+    let bmp = encode_bmp_to_vec(image).expect("Failed to write BMP");
+    decode(&bmp, w, h);
+
+    let jpg = encode_jpg_to_vec(image, quality).expect("Failed to write JPEG");
+    decode(&jpg, w, h);
+
+    let hdr_pixels: Vec<f32> = pixels.iter().map(|&p| p as f32 / 255.0).collect();
+    let hdr_image = ImageView::new(w, h, comp, &hdr_pixels).unwrap();
+    let hdr = encode_hdr_to_vec(hdr_image).expect("Failed to write HDR");
+    let (info, _) = stbi_loadf_from_memory(&hdr, Channels::Default).expect("Failed to decode HDR");
+    assert_eq!((info.width, info.height), (w, h));
+});
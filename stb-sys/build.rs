@@ -240,6 +240,9 @@ fn main() {
         builder.define("STBI_NO_PNM", "1");
     }
 
+    #[cfg(feature = "rust_allocator")]
+    builder.define("STB_RUST_ALLOCATOR", "1");
+
     match target.system.borrow() {
         "android" | "androideabi" => {
             let ndk = ndk();
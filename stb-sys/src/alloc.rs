@@ -0,0 +1,89 @@
+//! Rust global allocator hooks for `STBI_MALLOC`, `STBIW_MALLOC` and `STBIR_MALLOC` (see `stb_rust_alloc.h`).
+//!
+//! Rust deallocation requires the original layout, so every block is prefixed with a header
+//! holding the requested size. The header is `ALIGN` bytes long to keep the returned pointer
+//! aligned the same way `malloc` does.
+
+use std::alloc::{self, Layout};
+use std::os::raw::c_void;
+use std::ptr;
+
+/// Alignment of blocks returned to C, matches `max_align_t` on common platforms
+const ALIGN: usize = 16;
+
+fn layout(size: usize) -> Option<Layout> {
+    Layout::from_size_align(size.checked_add(ALIGN)?, ALIGN).ok()
+}
+
+/// Returns the start of the allocation and the size requested by C
+unsafe fn header(ptr: *mut c_void) -> (*mut u8, usize) {
+    let base = (ptr as *mut u8).sub(ALIGN);
+    (base, *(base as *const usize))
+}
+
+unsafe fn finish(base: *mut u8, size: usize) -> *mut c_void {
+    if base.is_null() {
+        return ptr::null_mut();
+    }
+
+    *(base as *mut usize) = size;
+    base.add(ALIGN) as *mut c_void
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_stb_malloc(size: usize) -> *mut c_void {
+    match layout(size) {
+        Some(layout) => finish(alloc::alloc(layout), size),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_stb_realloc(ptr: *mut c_void, size: usize) -> *mut c_void {
+    if ptr.is_null() {
+        return rust_stb_malloc(size);
+    }
+
+    let (base, old_size) = header(ptr);
+    match (layout(old_size), layout(size)) {
+        (Some(old_layout), Some(new_layout)) => {
+            finish(alloc::realloc(base, old_layout, new_layout.size()), size)
+        }
+        // Leave the original block untouched on failure, same as `realloc`
+        _ => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_stb_free(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let (base, size) = header(ptr);
+    if let Some(layout) = layout(size) {
+        alloc::dealloc(base, layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malloc_realloc_free() {
+        unsafe {
+            let ptr = rust_stb_malloc(4) as *mut u8;
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % ALIGN, 0);
+            ptr::copy_nonoverlapping([1_u8, 2, 3, 4].as_ptr(), ptr, 4);
+
+            let ptr = rust_stb_realloc(ptr as *mut c_void, 1024) as *mut u8;
+            assert!(!ptr.is_null());
+            assert_eq!(std::slice::from_raw_parts(ptr, 4), &[1, 2, 3, 4]);
+
+            rust_stb_free(ptr as *mut c_void);
+            rust_stb_free(ptr::null_mut());
+        }
+    }
+}
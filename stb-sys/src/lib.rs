@@ -6,3 +6,6 @@
 #![allow(clippy::excessive_precision)]
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+#[cfg(feature = "rust_allocator")]
+mod alloc;
@@ -1,4 +1,6 @@
 
+#include "stb_rust_alloc.h"
+
 #define STBI_NO_STDIO
 #define STB_IMAGE_IMPLEMENTATION
 #include "../vendor/stb/stb_image.h"
@@ -1,2 +1,4 @@
+#include "stb_rust_alloc.h"
+
 #define STB_IMAGE_RESIZE_IMPLEMENTATION
 #include "../vendor/stb/stb_image_resize.h"
\ No newline at end of file
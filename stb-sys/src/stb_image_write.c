@@ -1,2 +1,4 @@
+#include "stb_rust_alloc.h"
+
 #define STB_IMAGE_WRITE_IMPLEMENTATION
 #include "../vendor/stb/stb_image_write.h"
@@ -0,0 +1,25 @@
+#ifndef STB_RUST_ALLOC_H
+#define STB_RUST_ALLOC_H
+
+// Route stb allocations through Rust's global allocator when `rust_allocator` feature is enabled.
+// The functions are implemented in `src/alloc.rs`.
+#ifdef STB_RUST_ALLOCATOR
+#include <stddef.h>
+
+void *rust_stb_malloc(size_t size);
+void *rust_stb_realloc(void *ptr, size_t size);
+void rust_stb_free(void *ptr);
+
+#define STBI_MALLOC(sz) rust_stb_malloc(sz)
+#define STBI_REALLOC(p, newsz) rust_stb_realloc(p, newsz)
+#define STBI_FREE(p) rust_stb_free(p)
+
+#define STBIW_MALLOC(sz) rust_stb_malloc(sz)
+#define STBIW_REALLOC(p, newsz) rust_stb_realloc(p, newsz)
+#define STBIW_FREE(p) rust_stb_free(p)
+
+#define STBIR_MALLOC(size, context) ((void)(context), rust_stb_malloc(size))
+#define STBIR_FREE(ptr, context) ((void)(context), rust_stb_free(ptr))
+#endif
+
+#endif
@@ -1,2 +1,8 @@
+// Use stb_rect_pack for font atlas packing instead of the simple built-in packer.
+// The header is already part of the bindgen translation unit when stb_rect_pack.c is included first.
+#ifndef STB_INCLUDE_STB_RECT_PACK_H
+#include "../vendor/stb/stb_rect_pack.h"
+#endif
+
 #define STB_TRUETYPE_IMPLEMENTATION
-#include "../vendor/stb/stb_truetype.h"
\ No newline at end of file
+#include "../vendor/stb/stb_truetype.h"
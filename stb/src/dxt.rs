@@ -2,7 +2,10 @@
 
 use stb_sys as sys;
 
+use crate::image_view::ImageView;
+
 /// DXT compression mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum CompressionMode {
     /// Default compression mode
     Normal = 0,
@@ -44,6 +47,56 @@ pub fn stb_compress_bc5_block(dest: &mut [u8], src_rg_two_byte_per_pixel: &[u8])
     unsafe { sys::stb_compress_bc5_block(dest.as_mut_ptr(), src_rg_two_byte_per_pixel.as_ptr()) }
 }
 
+/// Compresses the whole `image`, padding partial blocks on the right and bottom edges by
+/// repeating the last column and row. The block format depends on the number of channels:
+///
+/// - 1 channel: BC4, 8 bytes per block
+/// - 2 channels: BC5, 16 bytes per block
+/// - 3 channels: DXT1 (BC1), 8 bytes per block
+/// - 4 channels: DXT5 (BC3), 16 bytes per block
+///
+/// Blocks are stored row by row. Returns `None` if the image has more than 4 channels.
+pub fn compress_image(image: ImageView<'_, u8>, mode: CompressionMode) -> Option<Vec<u8>> {
+    let channels = image.channels() as usize;
+    let block_size = match channels {
+        1 | 3 => 8,
+        2 | 4 => 16,
+        _ => return None,
+    };
+
+    let blocks_x = (image.width() as usize).div_ceil(4);
+    let blocks_y = (image.height() as usize).div_ceil(4);
+    let mut output = vec![0_u8; blocks_x * blocks_y * block_size];
+
+    // DXT1/DXT5 take RGBA input, BC4 and BC5 take source channels as is
+    let src_channels = if channels >= 3 { 4 } else { channels };
+    let mut src = [255_u8; 16 * 4];
+
+    for (index, dest) in output.chunks_exact_mut(block_size).enumerate() {
+        let block_x = (index % blocks_x) as i32 * 4;
+        let block_y = (index / blocks_x) as i32 * 4;
+
+        for y in 0..4 {
+            for x in 0..4 {
+                let pixel = image.pixel(
+                    (block_x + x).min(image.width() - 1),
+                    (block_y + y).min(image.height() - 1),
+                );
+                let offset = (y * 4 + x) as usize * src_channels;
+                src[offset..offset + channels].copy_from_slice(pixel);
+            }
+        }
+
+        match channels {
+            1 => stb_compress_bc4_block(dest, &src[..16]),
+            2 => stb_compress_bc5_block(dest, &src[..32]),
+            _ => stb_compress_dxt_block(dest, &src, (channels == 4) as i32, mode),
+        }
+    }
+
+    Some(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,4 +109,66 @@ mod tests {
         let mut dst: [u8; 8] = [0; 8];
         stb_compress_dxt_block(&mut dst, &src, 0, CompressionMode::Normal);
     }
+
+    #[test]
+    fn compress_image_blocks() {
+        // 5x3 image spans 2x1 blocks
+        let rgba = [128_u8; 5 * 3 * 4];
+        let view = ImageView::new(5, 3, 4, &rgba).unwrap();
+        let dxt5 = compress_image(view, CompressionMode::Normal).unwrap();
+        assert_eq!(dxt5.len(), 2 * 16);
+
+        let rgb = [128_u8; 5 * 3 * 3];
+        let view = ImageView::new(5, 3, 3, &rgb).unwrap();
+        let dxt1 = compress_image(view, CompressionMode::Highqual).unwrap();
+        assert_eq!(dxt1.len(), 2 * 8);
+
+        let grey = [128_u8; 5 * 3];
+        let view = ImageView::new(5, 3, 1, &grey).unwrap();
+        let bc4 = compress_image(view, CompressionMode::Normal).unwrap();
+        assert_eq!(bc4.len(), 2 * 8);
+
+        let view = ImageView::new(1, 1, 5, &grey).unwrap();
+        assert!(compress_image(view, CompressionMode::Normal).is_none());
+    }
+
+    /// Solid colour DXT1 block: both endpoints are the RGB565 colour, all indices are 2
+    fn solid_dxt1(rgb565: u16) -> [u8; 8] {
+        let [lo, hi] = rgb565.to_le_bytes();
+        [lo, hi, lo, hi, 0xaa, 0xaa, 0xaa, 0xaa]
+    }
+
+    #[test]
+    fn compress_image_solid() {
+        let red = [255_u8, 0, 0].repeat(16);
+        let view = ImageView::new(4, 4, 3, &red).unwrap();
+        let dxt1 = compress_image(view, CompressionMode::Normal).unwrap();
+        assert_eq!(dxt1, solid_dxt1(0xf800));
+    }
+
+    #[test]
+    fn compress_image_padding() {
+        // 5x5 image: red 4x4 block, blue last column, green last row and white corner
+        let mut rgb = Vec::new();
+        for y in 0..5 {
+            for x in 0..5 {
+                rgb.extend_from_slice(match (x, y) {
+                    (4, 4) => &[255, 255, 255],
+                    (4, _) => &[0, 0, 255],
+                    (_, 4) => &[0, 255, 0],
+                    _ => &[255, 0, 0],
+                });
+            }
+        }
+
+        // Edge blocks repeat the last column and row, so every block is solid
+        let view = ImageView::new(5, 5, 3, &rgb).unwrap();
+        let dxt1 = compress_image(view, CompressionMode::Normal).unwrap();
+        let blocks = dxt1.chunks_exact(8).collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[0], solid_dxt1(0xf800));
+        assert_eq!(blocks[1], solid_dxt1(0x001f));
+        assert_eq!(blocks[2], solid_dxt1(0x07e0));
+        assert_eq!(blocks[3], solid_dxt1(0xffff));
+    }
 }
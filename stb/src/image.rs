@@ -25,19 +25,42 @@
 //! So look for `stbi_xyz_from_reader` APIs instead of `stbi_xyz_from_callbacks`.
 //! -  There is no `Stdio` version of the API since it is convenient enough to use `stbi_xyz_from_reader`
 //! API from Rust and there is no need to pay C string conversion overhead.
+//! `info_path` and `load_xyz_path` helpers open the file and read it through a buffered reader.
+//! - Non-seekable readers (sockets, pipes, decompressors) are supported with `stbi_xyz_from_stream`
+//!   APIs, which emulate stb skips by reading ahead and replaying recently read bytes.
 //! - You can use `stbi_no_FORMAT` feature toggles to disable not needed image formats.
-
-use std::cmp::Ordering;
+//! - Info and load functions return `ImageError` on failure, which holds a copy of
+//!   `stbi_failure_reason` or the reader IO error.
+//! - Global setters like `stbi_set_flip_vertically_on_load` change process-wide state,
+//!   use `LoadOptions` to pass settings per call when decoding on multiple threads.
+//!   `LoadOptions::limits` rejects untrusted images declaring huge dimensions before decoding.
+//! - `Data::view` and `Image::view` return `ImageView`, which can be passed to `image_write`
+//!   encoders, `image_resize::resize` or `dxt::compress_image` as is. `Data::view` returns
+//!   `None` if stb reported invalid image dimensions.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::error;
 use std::ffi;
-use std::io;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
 use std::os::raw;
+use std::path::Path;
 use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use stb_sys as sys;
 
+#[cfg(feature = "stb_image_resize")]
+use crate::image_resize;
+pub use crate::image_view::ImageView;
+
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, PartialOrd, PartialEq, Eq)]
 pub enum Channels {
+    #[default]
     Default = 0,
     Grey = 1,
     GreyAlpha = 2,
@@ -55,12 +78,109 @@ pub struct Info {
     pub components: i32,
 }
 
+impl Info {
+    /// Returns the number of elements needed to hold decoded image with `desired_channels`.
+    /// Saturates at `usize::MAX` if the size overflows, stb can't decode such images anyway.
+    pub fn size(&self, desired_channels: Channels) -> usize {
+        usize::try_from(self.checked_size(desired_channels, 1).unwrap_or(u64::MAX))
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Returns the size of decoded image with `desired_channels` in units of `channel_size`,
+    /// `None` if it overflows `u64`
+    fn checked_size(&self, desired_channels: Channels, channel_size: u64) -> Option<u64> {
+        let channels = if desired_channels == Channels::Default {
+            self.components
+        } else {
+            desired_channels as i32
+        };
+
+        (self.width.max(0) as u64)
+            .checked_mul(self.height.max(0) as u64)?
+            .checked_mul(channels.max(0) as u64)?
+            .checked_mul(channel_size)
+    }
+}
+
+/// Image loading error
+#[derive(Debug)]
+pub enum ImageError {
+    /// Reader failed while stb was decoding the image
+    Io(io::Error),
+    /// Image format is unknown or disabled with `stbi_no_FORMAT` feature toggles
+    UnknownFormat,
+    /// stb failed to allocate memory for decoding
+    OutOfMemory,
+    /// Image is corrupt or uses unsupported format features, holds `stbi_failure_reason` message
+    Decode(String),
+    /// Output buffer can't hold decoded image
+    BufferTooSmall { required: usize, actual: usize },
+    /// Image header declares a size exceeding `Limits`, the image wasn't decoded
+    LimitExceeded { limit: Limit, value: u64, max: u64 },
+    /// Memory buffer is longer than stb can take (`int` length)
+    InputTooLarge { len: usize },
+}
+
+impl ImageError {
+    /// Captures `stbi_failure_reason` of the calling thread.
+    /// Must be called right after failed stb call, the message is overwritten by next failure.
+    fn from_failure_reason() -> Self {
+        let reason = unsafe { sys::stbi_failure_reason() };
+        if reason.is_null() {
+            return ImageError::Decode(String::from("unknown error"));
+        }
+
+        let reason = unsafe { ffi::CStr::from_ptr(reason) }.to_string_lossy();
+        match reason.as_ref() {
+            "unknown image type" => ImageError::UnknownFormat,
+            "outofmem" => ImageError::OutOfMemory,
+            _ => ImageError::Decode(reason.into_owned()),
+        }
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(err) => write!(f, "failed to read image: {}", err),
+            ImageError::UnknownFormat => f.write_str("unknown image format"),
+            ImageError::OutOfMemory => f.write_str("out of memory"),
+            ImageError::Decode(reason) => write!(f, "failed to decode image: {}", reason),
+            ImageError::BufferTooSmall { required, actual } => write!(
+                f,
+                "buffer too small: {} elements required, {} provided",
+                required, actual
+            ),
+            ImageError::LimitExceeded { limit, value, max } => {
+                write!(f, "image {} {} exceeds limit {}", limit, value, max)
+            }
+            ImageError::InputTooLarge { len } => {
+                write!(f, "input of {} bytes is too large to decode", len)
+            }
+        }
+    }
+}
+
+impl error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ImageError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(err: io::Error) -> Self {
+        ImageError::Io(err)
+    }
+}
+
 /// Holds image memory allocated by stb and responsible for calling `stbi_image_free` once dropped.
 pub struct Data<T> {
     data: *mut T,
     info: Info,
     desired_channels: Channels,
-    did_resize: bool,
 }
 
 impl<T> Data<T> {
@@ -69,52 +189,21 @@ impl<T> Data<T> {
             data,
             info,
             desired_channels,
-            did_resize: false,
         }
     }
 
-
-    pub fn info(&self) -> Info{
+    /// Returns image info as reported by stb
+    pub fn info(&self) -> Info {
         self.info
     }
 
-    #[cfg(feature = "stb_image_resize")]
-    pub fn resize(&mut self, x: i32, y: i32, desired_channels: Channels, info: Info) -> bool {
-        let width = self.info.width * x;
-        let height = self.info.height * y;
-
-        let components = if desired_channels == Channels::Default {
-            info.components
+    /// Returns the number of channels per pixel (either desired channels or image components)
+    pub fn channels(&self) -> i32 {
+        if self.desired_channels == Channels::Default {
+            self.info.components
         } else {
-            desired_channels as i32
-        };
-
-        let size = width * height * components;
-        let mut buf = vec![0_u8; size as usize];
-        let data = unsafe { slice::from_raw_parts(self.data.cast(), size as usize) };
-        let result = unsafe {
-            stb_sys::stbir_resize_uint8(
-                data.as_ptr(),
-                info.width,
-                info.height,
-                0,
-                buf.as_mut_ptr(),
-                width,
-                height,
-                0,
-                info.components,
-            )
-        };
-        if result == 0 {
-            return false;
+            self.desired_channels as i32
         }
-        self.info.width = width;
-        self.info.height = height;
-        unsafe {sys::stbi_image_free(self.data.cast())};
-        self.data = buf.as_mut_ptr().cast();
-        self.did_resize = true;
-        std::mem::forget(buf);
-        true
     }
 
     /// Returns image memory as a slice
@@ -123,20 +212,35 @@ impl<T> Data<T> {
         unsafe { slice::from_raw_parts(self.data, size) }
     }
 
-    pub fn as_mut_slice(&self) -> &mut [T] {
+    /// Returns image memory as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
         let size = self.size();
         unsafe { slice::from_raw_parts_mut(self.data, size) }
     }
 
     /// Returns the number of elements (which is width x height x desired_channels or components)
     pub fn size(&self) -> usize {
-        let components = if self.desired_channels == Channels::Default {
-            self.info.components
-        } else {
-            self.desired_channels as i32
-        };
+        // stb checks the image size fits in `int` before allocating, so this never falls back to 0
+        self.checked_size().unwrap_or(0)
+    }
+
+    fn checked_size(&self) -> Option<usize> {
+        usize::try_from(self.info.width)
+            .ok()?
+            .checked_mul(usize::try_from(self.info.height).ok()?)?
+            .checked_mul(usize::try_from(self.channels()).ok()?)
+    }
 
-        (self.info.width * self.info.height * components) as usize
+    /// Returns view of image pixels, which can be passed to encoders, resize or DXT compression.
+    /// Returns `None` if image dimensions reported by stb are invalid.
+    pub fn view(&self) -> Option<ImageView<'_, T>> {
+        self.checked_size()?;
+        ImageView::new(
+            self.info.width,
+            self.info.height,
+            self.channels(),
+            self.as_slice(),
+        )
     }
 }
 
@@ -145,22 +249,430 @@ impl<T: Clone> Data<T> {
     pub fn into_vec(self) -> Vec<T> {
         self.as_slice().to_vec()
     }
+
+    /// Copies stb memory into Rust owned image
+    pub fn into_image(self) -> Image<T> {
+        let width = self.info.width;
+        let height = self.info.height;
+        let channels = self.channels();
+
+        Image {
+            width,
+            height,
+            channels,
+            stride: (width * channels) as usize,
+            data: self.into_vec(),
+        }
+    }
 }
 
 impl<T> Drop for Data<T> {
     fn drop(&mut self) {
-        if self.did_resize {
-            unsafe { Vec::from_raw_parts(self.data, self.size(), self.size()) };
-            return;
-        }
         unsafe { sys::stbi_image_free(self.data as *mut ffi::c_void) };
     }
 }
 
-/// IO wrapper for stb
+impl<T: Clone> From<Data<T>> for Image<T> {
+    fn from(data: Data<T>) -> Self {
+        data.into_image()
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for f32 {}
+}
+
+/// Channel component types produced by stb decoders (`u8`, `u16` and `f32`)
+pub trait Sample: private::Sealed + Copy + Default {
+    /// Fully opaque alpha value
+    const MAX: Self;
+
+    /// Luminance of an RGB pixel, same weights as stb uses when converting to grey
+    fn luminance(r: Self, g: Self, b: Self) -> Self;
+}
+
+impl Sample for u8 {
+    const MAX: Self = u8::MAX;
+
+    fn luminance(r: Self, g: Self, b: Self) -> Self {
+        ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8
+    }
+}
+
+impl Sample for u16 {
+    const MAX: Self = u16::MAX;
+
+    fn luminance(r: Self, g: Self, b: Self) -> Self {
+        ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u16
+    }
+}
+
+impl Sample for f32 {
+    const MAX: Self = 1.0;
+
+    fn luminance(r: Self, g: Self, b: Self) -> Self {
+        (r * 77.0 + g * 150.0 + b * 29.0) / 256.0
+    }
+}
+
+/// Rust owned image, rows are `stride` elements apart (`stride` >= `width * channels`).
+///
+/// Unlike `Data`, this type doesn't hold any stb memory, so it can be freely mutated,
+/// post-processed (resized, flipped, converted to other channels) and moved across threads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image<T> {
+    width: i32,
+    height: i32,
+    channels: i32,
+    stride: usize,
+    data: Vec<T>,
+}
+
+impl<T> Image<T> {
+    /// Returns image of the same layout holding `data`
+    fn with_data<U>(&self, data: Vec<U>) -> Image<U> {
+        Image {
+            width: self.width,
+            height: self.height,
+            channels: self.channels,
+            stride: self.stride,
+            data,
+        }
+    }
+}
+
+impl<T: Sample> Image<T> {
+    /// Creates zero filled image with tightly packed rows.
+    /// Returns `None` if dimensions or channels are not positive.
+    pub fn new(width: i32, height: i32, channels: i32) -> Option<Self> {
+        if width <= 0 || height <= 0 || channels <= 0 {
+            return None;
+        }
+
+        let stride = (width as usize).checked_mul(channels as usize)?;
+        let size = stride.checked_mul(height as usize)?;
+
+        Some(Image {
+            width,
+            height,
+            channels,
+            stride,
+            data: vec![T::default(); size],
+        })
+    }
+
+    /// Wraps existing pixel data with tightly packed rows.
+    /// Returns `None` if `data` length doesn't match image dimensions.
+    pub fn from_vec(width: i32, height: i32, channels: i32, data: Vec<T>) -> Option<Self> {
+        let stride = (width.max(0) as usize).saturating_mul(channels.max(0) as usize);
+        Self::from_vec_with_stride(width, height, channels, stride, data)
+    }
+
+    /// Wraps existing pixel data, rows are `stride` elements apart.
+    /// Returns `None` if `stride` is shorter than a row or `data` is shorter than `stride * height`.
+    pub fn from_vec_with_stride(
+        width: i32,
+        height: i32,
+        channels: i32,
+        stride: usize,
+        data: Vec<T>,
+    ) -> Option<Self> {
+        if width <= 0 || height <= 0 || channels <= 0 {
+            return None;
+        }
+
+        let row = (width as usize).checked_mul(channels as usize)?;
+        if stride < row {
+            return None;
+        }
+
+        let size = stride.checked_mul(height as usize)?;
+        if data.len() < size {
+            return None;
+        }
+
+        Some(Image {
+            width,
+            height,
+            channels,
+            stride,
+            data,
+        })
+    }
+
+    /// Image width in pixels
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Number of channels per pixel
+    pub fn channels(&self) -> i32 {
+        self.channels
+    }
+
+    /// Distance between rows in elements
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Returns image memory as a slice (including row padding, if any)
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns image memory as a mutable slice (including row padding, if any)
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Consumes this image into underlying vector
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Returns view of image pixels, which can be passed to encoders, resize or DXT compression
+    pub fn view(&self) -> ImageView<'_, T> {
+        ImageView::with_stride(
+            self.width,
+            self.height,
+            self.channels,
+            self.stride,
+            &self.data,
+        )
+        .expect("image invariants are checked on construction")
+    }
+
+    /// Returns pixels of the row `y` (without padding)
+    pub fn row(&self, y: i32) -> &[T] {
+        let range = self.row_range(y);
+        &self.data[range]
+    }
+
+    /// Returns mutable pixels of the row `y` (without padding)
+    pub fn row_mut(&mut self, y: i32) -> &mut [T] {
+        let range = self.row_range(y);
+        &mut self.data[range]
+    }
+
+    /// Returns channels of the pixel at (x, y)
+    pub fn pixel(&self, x: i32, y: i32) -> &[T] {
+        assert!(x >= 0 && x < self.width, "x is out of bounds");
+
+        let channels = self.channels as usize;
+        let offset = x as usize * channels;
+        &self.row(y)[offset..offset + channels]
+    }
+
+    fn row_range(&self, y: i32) -> std::ops::Range<usize> {
+        assert!(y >= 0 && y < self.height, "y is out of bounds");
+
+        let start = y as usize * self.stride;
+        start..start + self.width as usize * self.channels as usize
+    }
+
+    /// Flips the image vertically in place, so the first row becomes the last one
+    pub fn flip_vertically(&mut self) {
+        let row = self.width as usize * self.channels as usize;
+        let (mut top, mut bottom) = (0, self.height as usize - 1);
+
+        while top < bottom {
+            let (head, tail) = self.data.split_at_mut(bottom * self.stride);
+            head[top * self.stride..top * self.stride + row].swap_with_slice(&mut tail[..row]);
+
+            top += 1;
+            bottom -= 1;
+        }
+    }
+
+    /// Converts image to the desired number of channels the same way stb does on load:
+    /// grey is replicated to RGB, RGB is converted to grey with luminance weights and
+    /// missing alpha is set to fully opaque.
+    /// Returns `None` if the image doesn't have 1..=4 channels.
+    pub fn convert_channels(&self, desired_channels: Channels) -> Option<Image<T>> {
+        if self.channels < 1 || self.channels > 4 {
+            return None;
+        }
+
+        if desired_channels == Channels::Default || desired_channels as i32 == self.channels {
+            return Some(self.clone());
+        }
+
+        let mut image = Image::new(self.width, self.height, desired_channels as i32)?;
+        let src_channels = self.channels as usize;
+        let dst_channels = desired_channels as usize;
+
+        for y in 0..self.height {
+            let src = self.row(y).chunks_exact(src_channels);
+            let dst = image.row_mut(y).chunks_exact_mut(dst_channels);
+
+            for (src, dst) in src.zip(dst) {
+                let (grey, rgb, alpha) = match *src {
+                    [g] => (g, [g, g, g], T::MAX),
+                    [g, a] => (g, [g, g, g], a),
+                    [r, g, b] => (T::luminance(r, g, b), [r, g, b], T::MAX),
+                    [r, g, b, a] => (T::luminance(r, g, b), [r, g, b], a),
+                    _ => unreachable!(),
+                };
+
+                match dst {
+                    [g] => *g = grey,
+                    [g, a] => {
+                        *g = grey;
+                        *a = alpha;
+                    }
+                    [r, g, b] => {
+                        *r = rgb[0];
+                        *g = rgb[1];
+                        *b = rgb[2];
+                    }
+                    [r, g, b, a] => {
+                        *r = rgb[0];
+                        *g = rgb[1];
+                        *b = rgb[2];
+                        *a = alpha;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        Some(image)
+    }
+}
+
+#[cfg(feature = "stb_image_resize")]
+impl<T: Sample + image_resize::Component> Image<T> {
+    /// Resizes image to the given dimensions with default filter and edge modes
+    pub fn resize(&self, width: i32, height: i32) -> Option<Image<T>> {
+        self.resize_with(width, height, &image_resize::Options::default())
+    }
+
+    /// Resizes image to the given dimensions with explicit resize options
+    pub fn resize_with(
+        &self,
+        width: i32,
+        height: i32,
+        options: &image_resize::Options,
+    ) -> Option<Image<T>> {
+        let data = image_resize::resize(self.view(), width, height, options)?;
+        Image::from_vec(width, height, self.channels, data)
+    }
+}
+
+/// Frames of animated GIF image allocated by stb, frames are stored one after another.
+/// Responsible for freeing both frames and delays memory once dropped.
+#[cfg(not(feature = "stbi_no_gif"))]
+pub struct AnimatedImage {
+    data: *mut u8,
+    delays: *mut raw::c_int,
+    width: i32,
+    height: i32,
+    frames: i32,
+    channels: i32,
+}
+
+#[cfg(not(feature = "stbi_no_gif"))]
+impl AnimatedImage {
+    /// Frame width in pixels
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Frame height in pixels
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Number of channels per pixel (either desired channels or 4)
+    pub fn channels(&self) -> i32 {
+        self.channels
+    }
+
+    /// Number of frames in the animation
+    pub fn frame_count(&self) -> usize {
+        usize::try_from(self.frames).unwrap_or(0)
+    }
+
+    /// Number of elements in a single frame (width x height x channels),
+    /// 0 if dimensions are negative or the size overflows `usize`
+    pub fn frame_size(&self) -> usize {
+        self.checked_frame_size().unwrap_or(0)
+    }
+
+    fn checked_frame_size(&self) -> Option<usize> {
+        usize::try_from(self.width)
+            .ok()?
+            .checked_mul(usize::try_from(self.height).ok()?)?
+            .checked_mul(usize::try_from(self.channels).ok()?)
+    }
+
+    /// Returns all frames memory as a slice
+    pub fn as_slice(&self) -> &[u8] {
+        let size = self.frame_size().checked_mul(self.frame_count());
+        match size {
+            Some(size) if !self.data.is_null() => unsafe { slice::from_raw_parts(self.data, size) },
+            _ => &[],
+        }
+    }
+
+    /// Returns pixels of the frame `index`
+    pub fn frame(&self, index: usize) -> Option<&[u8]> {
+        self.frames().nth(index)
+    }
+
+    /// Returns view of the frame `index` pixels, `None` if out of range
+    pub fn frame_view(&self, index: usize) -> Option<ImageView<'_, u8>> {
+        ImageView::new(self.width, self.height, self.channels, self.frame(index)?)
+    }
+
+    /// Returns iterator over frame pixels
+    pub fn frames(&self) -> slice::ChunksExact<'_, u8> {
+        self.as_slice().chunks_exact(self.frame_size().max(1))
+    }
+
+    /// Returns per-frame delays in milliseconds
+    pub fn delays(&self) -> &[i32] {
+        if self.delays.is_null() {
+            return &[];
+        }
+
+        unsafe { slice::from_raw_parts(self.delays, self.frame_count()) }
+    }
+
+    /// Returns delay of the frame `index` in milliseconds
+    pub fn delay(&self, index: usize) -> Option<i32> {
+        self.delays().get(index).copied()
+    }
+}
+
+#[cfg(not(feature = "stbi_no_gif"))]
+impl Drop for AnimatedImage {
+    fn drop(&mut self) {
+        unsafe {
+            sys::stbi_image_free(self.data as *mut ffi::c_void);
+            sys::stbi_image_free(self.delays as *mut ffi::c_void);
+        }
+    }
+}
+
+/// IO wrapper for stb.
+///
+/// stb treats short reads as errors, so `read` keeps reading until the buffer is full or the
+/// reader is exhausted. End of data and IO errors are tracked separately: both stop stb from
+/// reading further, but only IO errors are reported back to the caller.
 struct Wrapper<'a, R> {
     reader: &'a mut R,
-    err: bool,
+    err: Option<io::Error>,
+    eof: bool,
 }
 
 impl<'a, R> Wrapper<'a, R>
@@ -168,7 +680,11 @@ where
     R: io::Read + io::Seek,
 {
     fn new(reader: &'a mut R) -> (Wrapper<'a, R>, sys::stbi_io_callbacks) {
-        let reader = Wrapper { reader, err: false };
+        let reader = Wrapper {
+            reader,
+            err: None,
+            eof: false,
+        };
         let callbacks = sys::stbi_io_callbacks {
             read: Some(Self::io_read),
             skip: Some(Self::io_skip),
@@ -182,8 +698,13 @@ where
         unsafe { &mut *(user as *mut Wrapper<R>) }
     }
 
+    /// Takes the first IO error occurred while stb was reading, if any
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.err.take()
+    }
+
     fn read(&mut self, data: *mut raw::c_char, size: raw::c_int) -> raw::c_int {
-        if self.err {
+        if self.err.is_some() || self.eof || size <= 0 {
             return 0;
         }
 
@@ -192,40 +713,43 @@ where
             slice::from_raw_parts_mut(data, size as _)
         };
 
-        if let Ok(n) = self.reader.read(dest) {
-            return n as _;
+        let mut total = 0;
+        while total < dest.len() {
+            match self.reader.read(&mut dest[total..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(n) => total += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.err = Some(err);
+                    break;
+                }
+            }
         }
 
-        self.err = true;
-        0
+        total as _
     }
 
     fn skip(&mut self, n: raw::c_int) {
-        match n.cmp(&0) {
-            Ordering::Greater => {
-                if self.reader.seek(io::SeekFrom::Current(n as _)).is_err() {
-                    self.err = true
-                }
-            }
-            Ordering::Less => {
-                // stb allows negative seeks while Rust API considers this as an error
-                if self
-                    .reader
-                    .seek(io::SeekFrom::Current(0)) // Find current position
-                    .and_then(|pos| self.reader.seek(io::SeekFrom::Start(pos - n as u64))) // Seek from start
-                    .is_err()
-                {
-                    self.err = true
+        if n == 0 || self.err.is_some() {
+            return;
+        }
+
+        // Rust allows negative relative seeks as long as the position stays non negative
+        match self.reader.seek(io::SeekFrom::Current(n as i64)) {
+            Ok(_) => {
+                if n < 0 {
+                    self.eof = false;
                 }
             }
-            _ => {
-                // Do nothing if zero
-            }
+            Err(err) => self.err = Some(err),
         }
     }
 
     fn eof(&self) -> raw::c_int {
-        if self.err {
+        if self.err.is_some() || self.eof {
             1
         } else {
             0
@@ -253,11 +777,123 @@ where
     }
 }
 
+/// Number of recently read bytes `Lookback` keeps around to serve backward skips
+const LOOKBACK_SIZE: usize = 4096;
+
+/// Adapts non-seekable reader to `Wrapper`, which is only asked for relative seeks.
+/// Forward seeks read and discard data, backward seeks replay bytes kept in a lookback buffer.
+struct Lookback<R> {
+    reader: R,
+    /// Last `LOOKBACK_SIZE` bytes returned by `read`
+    history: VecDeque<u8>,
+    /// Bytes moved back from `history` by backward seeks, returned by `read` first
+    pending: VecDeque<u8>,
+    position: u64,
+}
+
+impl<R: io::Read> Lookback<R> {
+    fn new(reader: R) -> Self {
+        Lookback {
+            reader,
+            history: VecDeque::with_capacity(LOOKBACK_SIZE),
+            pending: VecDeque::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for Lookback<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = if self.pending.is_empty() {
+            self.reader.read(buf)?
+        } else {
+            self.pending.read(buf)?
+        };
+
+        self.history.extend(&buf[..n]);
+        if self.history.len() > LOOKBACK_SIZE {
+            self.history.drain(..self.history.len() - LOOKBACK_SIZE);
+        }
+
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: io::Read> io::Seek for Lookback<R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match pos {
+            io::SeekFrom::Current(n) if n >= 0 => {
+                // Skipping past the end is not an error, next read just returns 0
+                io::copy(&mut self.by_ref().take(n as u64), &mut io::sink())?;
+            }
+            io::SeekFrom::Current(n) => {
+                let n = n.unsigned_abs() as usize;
+                if n > self.history.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "can't seek back further than lookback buffer",
+                    ));
+                }
+
+                for byte in self.history.drain(self.history.len() - n..).rev() {
+                    self.pending.push_front(byte);
+                }
+                self.position -= n as u64;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "stream only supports relative seeks",
+                ))
+            }
+        }
+
+        Ok(self.position)
+    }
+}
+
+/// Get image dimensions & components from non-seekable reader without fully decoding
+pub fn stbi_info_from_stream<R: io::Read>(reader: &mut R) -> Result<Info, ImageError> {
+    stbi_info_from_reader(&mut Lookback::new(reader))
+}
+
+/// 8-bits-per-channel interface, load image from non-seekable reader
+pub fn stbi_load_from_stream<R: io::Read>(
+    reader: &mut R,
+    desired_channels: Channels,
+) -> Result<(Info, Data<u8>), ImageError> {
+    stbi_load_from_reader(&mut Lookback::new(reader), desired_channels)
+}
+
+/// 16-bits-per-channel interface, load image from non-seekable reader
+pub fn stbi_load_16_from_stream<R: io::Read>(
+    reader: &mut R,
+    desired_channels: Channels,
+) -> Result<(Info, Data<u16>), ImageError> {
+    stbi_load_16_from_reader(&mut Lookback::new(reader), desired_channels)
+}
+
+/// Floating point interface, load image from non-seekable reader
+#[cfg(not(feature = "stbi_no_linear"))]
+pub fn stbi_loadf_from_stream<R: io::Read>(
+    reader: &mut R,
+    desired_channels: Channels,
+) -> Result<(Info, Data<f32>), ImageError> {
+    stbi_loadf_from_reader(&mut Lookback::new(reader), desired_channels)
+}
+
+// Global load settings, tracked so they can be copied to stb thread-local settings
+static FLIP_VERTICALLY_ON_LOAD: AtomicBool = AtomicBool::new(false);
+static CONVERT_IPHONE_PNG_TO_RGB: AtomicBool = AtomicBool::new(false);
+static UNPREMULTIPLY_ON_LOAD: AtomicBool = AtomicBool::new(false);
+
 /// By default we convert iphone-formatted PNGs back to RGB, even though they are internally
 /// encoded differently. You can disable this conversion by calling
 /// `stbi_convert_iphone_png_to_rgb(false)`, in which case you will always just get the
 /// native iphone "format" through (which is BGR stored in RGB).
 pub fn stbi_convert_iphone_png_to_rgb(true_if_should_convert: bool) {
+    CONVERT_IPHONE_PNG_TO_RGB.store(true_if_should_convert, Ordering::Relaxed);
     unsafe { sys::stbi_convert_iphone_png_to_rgb(if true_if_should_convert { 1 } else { 0 }) }
 }
 
@@ -265,36 +901,115 @@ pub fn stbi_convert_iphone_png_to_rgb(true_if_should_convert: bool) {
 /// premultiplied alpha *only* if the image file explicitly says there's premultiplied
 /// data (currently only happens in iPhone images, and only if iPhone convert-to-rgb processing is on).
 pub fn stbi_set_unpremultiply_on_load(true_if_should_unpremultiply: bool) {
+    UNPREMULTIPLY_ON_LOAD.store(true_if_should_unpremultiply, Ordering::Relaxed);
     unsafe { sys::stbi_set_unpremultiply_on_load(if true_if_should_unpremultiply { 1 } else { 0 }) }
 }
 
 /// Flip the image vertically, so the first pixel in the output array is the bottom left
 pub fn stbi_set_flip_vertically_on_load(true_if_should_flip: bool) {
+    FLIP_VERTICALLY_ON_LOAD.store(true_if_should_flip, Ordering::Relaxed);
     unsafe { sys::stbi_set_flip_vertically_on_load(if true_if_should_flip { 1 } else { 0 }) }
 }
 
-/// Get image dimensions & components from a slice without fully decoding
-pub fn stbi_info_from_memory(buffer: &[u8]) -> Option<Info> {
-    let mut info = Info::default();
-    let ret = unsafe {
-        sys::stbi_info_from_memory(
-            buffer.as_ptr(),
-            buffer.len() as i32,
-            &mut info.width,
-            &mut info.height,
-            &mut info.components,
-        )
-    };
-    if ret == 0 {
-        None
-    } else {
-        Some(info)
-    }
+/// Load settings stb can set per thread
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct LoadSettings {
+    flip_vertically: bool,
+    convert_iphone_png_to_rgb: bool,
+    unpremultiply: bool,
 }
 
-/// Get image dimensions & components from reader without fully decoding
-pub fn stbi_info_from_reader<R>(reader: &mut R) -> Option<Info>
-where
+impl LoadSettings {
+    /// Current global settings
+    fn global() -> Self {
+        Self {
+            flip_vertically: FLIP_VERTICALLY_ON_LOAD.load(Ordering::Relaxed),
+            convert_iphone_png_to_rgb: CONVERT_IPHONE_PNG_TO_RGB.load(Ordering::Relaxed),
+            unpremultiply: UNPREMULTIPLY_ON_LOAD.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets stb thread-local settings, which take precedence over global ones on this thread
+    fn apply_thread(self) {
+        unsafe {
+            sys::stbi_set_flip_vertically_on_load_thread(self.flip_vertically as raw::c_int);
+            sys::stbi_convert_iphone_png_to_rgb_thread(
+                self.convert_iphone_png_to_rgb as raw::c_int,
+            );
+            sys::stbi_set_unpremultiply_on_load_thread(self.unpremultiply as raw::c_int);
+        }
+    }
+}
+
+/// Settings stb uses for loads on the current thread
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ThreadSettings {
+    /// stb thread-local settings were never set, stb reads global settings
+    Unset,
+    /// stb thread-local settings were set before and can't be unset,
+    /// global settings are copied to them before each load
+    Global,
+    /// `LoadOptions` load is in progress on this thread
+    Pinned(LoadSettings),
+}
+
+thread_local! {
+    static THREAD_SETTINGS: Cell<ThreadSettings> = const { Cell::new(ThreadSettings::Unset) };
+}
+
+/// Sets stb thread-local settings for the next load on this thread, if needed
+fn apply_thread_settings() {
+    match THREAD_SETTINGS.with(Cell::get) {
+        ThreadSettings::Unset => {}
+        ThreadSettings::Global => LoadSettings::global().apply_thread(),
+        ThreadSettings::Pinned(settings) => settings.apply_thread(),
+    }
+}
+
+/// Pins settings for all loads on this thread until dropped
+struct PinnedSettings;
+
+impl PinnedSettings {
+    fn new(settings: LoadSettings) -> Self {
+        THREAD_SETTINGS.with(|s| s.set(ThreadSettings::Pinned(settings)));
+        PinnedSettings
+    }
+}
+
+impl Drop for PinnedSettings {
+    fn drop(&mut self) {
+        THREAD_SETTINGS.with(|s| s.set(ThreadSettings::Global));
+    }
+}
+
+/// stb takes memory buffer length as `int`
+fn buffer_len(buffer: &[u8]) -> Result<raw::c_int, ImageError> {
+    raw::c_int::try_from(buffer.len()).map_err(|_| ImageError::InputTooLarge { len: buffer.len() })
+}
+
+/// Get image dimensions & components from a slice without fully decoding
+pub fn stbi_info_from_memory(buffer: &[u8]) -> Result<Info, ImageError> {
+    let len = buffer_len(buffer)?;
+    let mut info = Info::default();
+    let ret = unsafe {
+        sys::stbi_info_from_memory(
+            buffer.as_ptr(),
+            len,
+            &mut info.width,
+            &mut info.height,
+            &mut info.components,
+        )
+    };
+    if ret == 0 {
+        Err(ImageError::from_failure_reason())
+    } else {
+        Ok(info)
+    }
+}
+
+/// Get image dimensions & components from reader without fully decoding
+pub fn stbi_info_from_reader<R>(reader: &mut R) -> Result<Info, ImageError>
+where
     R: io::Read + io::Seek,
 {
     let (mut reader, callbacks) = Wrapper::new(reader);
@@ -310,19 +1025,22 @@ where
         )
     };
 
-    if ret == 0 {
-        None
+    if let Some(err) = reader.take_error() {
+        Err(ImageError::Io(err))
+    } else if ret == 0 {
+        Err(ImageError::from_failure_reason())
     } else {
-        Some(info)
+        Ok(info)
     }
 }
 
-pub fn stbi_is_16_bit_from_memory(buffer: &[u8]) -> bool {
-    let ret = unsafe { sys::stbi_is_16_bit_from_memory(buffer.as_ptr(), buffer.len() as i32) };
-    ret == 1
+pub fn stbi_is_16_bit_from_memory(buffer: &[u8]) -> Result<bool, ImageError> {
+    let len = buffer_len(buffer)?;
+    let ret = unsafe { sys::stbi_is_16_bit_from_memory(buffer.as_ptr(), len) };
+    Ok(ret == 1)
 }
 
-pub fn stbi_is_16_bit_from_reader<R>(reader: &mut R) -> bool
+pub fn stbi_is_16_bit_from_reader<R>(reader: &mut R) -> io::Result<bool>
 where
     R: io::Read + io::Seek,
 {
@@ -330,19 +1048,282 @@ where
     let ret = unsafe {
         sys::stbi_is_16_bit_from_callbacks(&callbacks, &mut reader as *mut _ as *mut ffi::c_void)
     };
-    ret == 1
+
+    match reader.take_error() {
+        Some(err) => Err(err),
+        None => Ok(ret == 1),
+    }
+}
+
+/// Returns true if the image is HDR (Radiance rgbE format)
+pub fn stbi_is_hdr_from_memory(buffer: &[u8]) -> Result<bool, ImageError> {
+    let len = buffer_len(buffer)?;
+    let ret = unsafe { sys::stbi_is_hdr_from_memory(buffer.as_ptr(), len) };
+    Ok(ret == 1)
+}
+
+/// Returns true if the image is HDR (Radiance rgbE format)
+pub fn stbi_is_hdr_from_reader<R>(reader: &mut R) -> io::Result<bool>
+where
+    R: io::Read + io::Seek,
+{
+    let (mut reader, callbacks) = Wrapper::new(reader);
+    let ret = unsafe {
+        sys::stbi_is_hdr_from_callbacks(&callbacks, &mut reader as *mut _ as *mut ffi::c_void)
+    };
+
+    match reader.take_error() {
+        Some(err) => Err(err),
+        None => Ok(ret == 1),
+    }
+}
+
+/// Image container formats stb can decode
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Psd,
+    Gif,
+    Hdr,
+    Pic,
+    Pnm,
+    Tga,
+}
+
+impl ImageFormat {
+    /// Formats in the order stb probes them, TGA goes last since it has no signature
+    const PROBE_ORDER: [ImageFormat; 9] = [
+        ImageFormat::Jpeg,
+        ImageFormat::Png,
+        ImageFormat::Bmp,
+        ImageFormat::Gif,
+        ImageFormat::Psd,
+        ImageFormat::Pic,
+        ImageFormat::Pnm,
+        ImageFormat::Hdr,
+        ImageFormat::Tga,
+    ];
+
+    /// Number of leading bytes needed to recognize any format (PIC tag is at offset 88)
+    const HEADER_SIZE: usize = 92;
+
+    /// Returns false if decoding of this format is disabled with `stbi_no_FORMAT` feature toggle
+    pub fn is_enabled(self) -> bool {
+        match self {
+            ImageFormat::Png => cfg!(not(feature = "stbi_no_png")),
+            ImageFormat::Jpeg => cfg!(not(feature = "stbi_no_jpeg")),
+            ImageFormat::Bmp => cfg!(not(feature = "stbi_no_bmp")),
+            ImageFormat::Psd => cfg!(not(feature = "stbi_no_psd")),
+            ImageFormat::Gif => cfg!(not(feature = "stbi_no_gif")),
+            ImageFormat::Hdr => cfg!(not(feature = "stbi_no_hdr")),
+            ImageFormat::Pic => cfg!(not(feature = "stbi_no_pic")),
+            ImageFormat::Pnm => cfg!(not(feature = "stbi_no_pnm")),
+            ImageFormat::Tga => true,
+        }
+    }
+
+    /// Same checks as `stbi__xyz_test` functions.
+    /// Like stb, reads past the end of the header yield zeros.
+    fn matches(self, header: &[u8]) -> bool {
+        let byte = |i: usize| header.get(i).copied().unwrap_or(0);
+        let u16le = |i: usize| byte(i) as u16 | (byte(i + 1) as u16) << 8;
+        let u32le = |i: usize| u16le(i) as u32 | (u16le(i + 2) as u32) << 16;
+
+        match self {
+            ImageFormat::Png => header.starts_with(b"\x89PNG\r\n\x1a\n"),
+            ImageFormat::Jpeg => header.starts_with(&[0xFF, 0xD8]),
+            ImageFormat::Bmp => {
+                header.starts_with(b"BM") && matches!(u32le(14), 12 | 40 | 56 | 108 | 124)
+            }
+            ImageFormat::Psd => header.starts_with(b"8BPS"),
+            ImageFormat::Gif => {
+                header.starts_with(b"GIF8") && matches!(byte(4), b'7' | b'9') && byte(5) == b'a'
+            }
+            ImageFormat::Hdr => {
+                header.starts_with(b"#?RADIANCE\n") || header.starts_with(b"#?RGBE\n")
+            }
+            ImageFormat::Pic => {
+                header.starts_with(&[0x53, 0x80, 0xF6, 0x34])
+                    && header.get(88..92) == Some(&b"PICT"[..])
+            }
+            ImageFormat::Pnm => byte(0) == b'P' && matches!(byte(1), b'5' | b'6'),
+            ImageFormat::Tga => {
+                let color_type = byte(1);
+                let image_type = byte(2);
+
+                let header_ok = match color_type {
+                    0 => matches!(image_type, 2 | 3 | 10 | 11),
+                    1 => matches!(image_type, 1 | 9) && matches!(byte(7), 8 | 15 | 16 | 24 | 32),
+                    _ => false,
+                };
+
+                let bits_per_pixel = byte(16);
+                header_ok
+                    && u16le(12) >= 1
+                    && u16le(14) >= 1
+                    && (color_type == 0 || matches!(bits_per_pixel, 8 | 16))
+                    && matches!(bits_per_pixel, 8 | 15 | 16 | 24 | 32)
+            }
+        }
+    }
+}
+
+/// Detect image format from its leading bytes, the same way stb picks a decoder.
+/// Returns `None` if the format is unknown or disabled with `stbi_no_FORMAT` feature toggles.
+pub fn detect_format(buffer: &[u8]) -> Option<ImageFormat> {
+    ImageFormat::PROBE_ORDER
+        .iter()
+        .copied()
+        .find(|format| format.is_enabled() && format.matches(buffer))
+}
+
+/// Detect image format from reader, reads a few leading bytes and seeks back.
+pub fn detect_format_from_reader<R>(reader: &mut R) -> io::Result<Option<ImageFormat>>
+where
+    R: io::Read + io::Seek,
+{
+    let start = reader.stream_position()?;
+
+    let mut header = Vec::with_capacity(ImageFormat::HEADER_SIZE);
+    reader
+        .by_ref()
+        .take(ImageFormat::HEADER_SIZE as u64)
+        .read_to_end(&mut header)?;
+
+    reader.seek(io::SeekFrom::Start(start))?;
+
+    Ok(detect_format(&header))
+}
+
+/// Image properties gathered by `probe` without decoding pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProbeInfo {
+    /// Image width in pixels
+    pub width: i32,
+    /// Image height in pixels
+    pub height: i32,
+    /// Number of image components in image file
+    pub components: i32,
+    /// Image has 16 bits per channel (PNG, PSD and PNM only)
+    pub is_16_bit: bool,
+    /// Image is HDR, `stbi_loadf_*` returns original values without LDR conversion
+    pub is_hdr: bool,
+    /// Detected container format
+    pub format: Option<ImageFormat>,
+}
+
+impl ProbeInfo {
+    /// Returns dimensions and components only
+    pub fn info(&self) -> Info {
+        Info {
+            width: self.width,
+            height: self.height,
+            components: self.components,
+        }
+    }
+
+    fn new(info: Info, format: Option<ImageFormat>, is_16_bit: bool) -> Self {
+        ProbeInfo {
+            width: info.width,
+            height: info.height,
+            components: info.components,
+            is_16_bit,
+            is_hdr: format == Some(ImageFormat::Hdr),
+            format,
+        }
+    }
+}
+
+/// Only these formats can have 16 bits per channel, skip parsing header again for the rest
+fn may_be_16_bit(format: Option<ImageFormat>) -> bool {
+    matches!(
+        format,
+        None | Some(ImageFormat::Png) | Some(ImageFormat::Psd) | Some(ImageFormat::Pnm)
+    )
+}
+
+/// Get image dimensions, components, bit depth and format from a slice without fully decoding
+pub fn probe(buffer: &[u8]) -> Result<ProbeInfo, ImageError> {
+    let info = stbi_info_from_memory(buffer)?;
+    let format = detect_format(buffer);
+    let is_16_bit = may_be_16_bit(format) && stbi_is_16_bit_from_memory(buffer)?;
+
+    Ok(ProbeInfo::new(info, format, is_16_bit))
+}
+
+/// Get image dimensions, components, bit depth and format from reader without fully decoding.
+/// The reader is rewound back to its original position, so the image can be decoded next.
+pub fn probe_from_reader<R>(reader: &mut R) -> Result<ProbeInfo, ImageError>
+where
+    R: io::Read + io::Seek,
+{
+    let start = reader.stream_position()?;
+
+    let info = stbi_info_from_reader(reader);
+    reader.seek(io::SeekFrom::Start(start))?;
+    let info = info?;
+
+    let format = detect_format_from_reader(reader)?;
+
+    let is_16_bit = if may_be_16_bit(format) {
+        let is_16_bit = stbi_is_16_bit_from_reader(reader)?;
+        reader.seek(io::SeekFrom::Start(start))?;
+        is_16_bit
+    } else {
+        false
+    };
+
+    Ok(ProbeInfo::new(info, format, is_16_bit))
+}
+
+/// Wraps memory returned by stb load functions, captures failure reason if it's null
+fn load_result<T>(
+    data: *mut T,
+    desired_channels: Channels,
+    info: Info,
+) -> Result<(Info, Data<T>), ImageError> {
+    if data.is_null() {
+        Err(ImageError::from_failure_reason())
+    } else {
+        Ok((info, Data::new(data, desired_channels, info)))
+    }
+}
+
+/// Same as `load_result`, but reports reader IO errors first.
+/// stb can't tell IO errors apart from truncated data, so the image is discarded
+/// if the reader failed, even if stb managed to decode something.
+fn load_reader_result<T, R>(
+    reader: &mut Wrapper<R>,
+    data: *mut T,
+    desired_channels: Channels,
+    info: Info,
+) -> Result<(Info, Data<T>), ImageError>
+where
+    R: io::Read + io::Seek,
+{
+    let result = load_result(data, desired_channels, info);
+
+    match reader.take_error() {
+        Some(err) => Err(ImageError::Io(err)),
+        None => result,
+    }
 }
 
+/// 8-bits-per-channel interface, load image from memory
 pub fn stbi_load_from_memory(
     buffer: &[u8],
     desired_channels: Channels,
-) -> Option<(Info, Data<u8>)> {
+) -> Result<(Info, Data<u8>), ImageError> {
+    let len = buffer_len(buffer)?;
     let mut info = Info::default();
 
+    apply_thread_settings();
     let data = unsafe {
         sys::stbi_load_from_memory(
             buffer.as_ptr(),
-            buffer.len() as i32,
+            len,
             &mut info.width,
             &mut info.height,
             &mut info.components,
@@ -350,24 +1331,21 @@ pub fn stbi_load_from_memory(
         )
     };
 
-    if data.is_null() {
-        None
-    } else {
-        Some((info, Data::new(data, desired_channels, info)))
-    }
+    load_result(data, desired_channels, info)
 }
 
 /// 8-bits-per-channel interface, load image from reader
 pub fn stbi_load_from_reader<R>(
     reader: &mut R,
     desired_channels: Channels,
-) -> Option<(Info, Data<u8>)>
+) -> Result<(Info, Data<u8>), ImageError>
 where
     R: io::Read + io::Seek,
 {
     let (mut reader, callbacks) = Wrapper::new(reader);
     let mut info = Info::default();
 
+    apply_thread_settings();
     let data = unsafe {
         sys::stbi_load_from_callbacks(
             &callbacks,
@@ -379,24 +1357,22 @@ where
         )
     };
 
-    if data.is_null() {
-        None
-    } else {
-        Some((info, Data::new(data, desired_channels, info)))
-    }
+    load_reader_result(&mut reader, data, desired_channels, info)
 }
 
 /// 16-bits-per-channel interface, load image from memory
 pub fn stbi_load_16_from_memory(
     buffer: &[u8],
     desired_channels: Channels,
-) -> Option<(Info, Data<u16>)> {
+) -> Result<(Info, Data<u16>), ImageError> {
+    let len = buffer_len(buffer)?;
     let mut info = Info::default();
 
+    apply_thread_settings();
     let data = unsafe {
         sys::stbi_load_16_from_memory(
             buffer.as_ptr(),
-            buffer.len() as i32,
+            len,
             &mut info.width,
             &mut info.height,
             &mut info.components,
@@ -404,23 +1380,21 @@ pub fn stbi_load_16_from_memory(
         )
     };
 
-    if data.is_null() {
-        None
-    } else {
-        Some((info, Data::new(data, desired_channels, info)))
-    }
+    load_result(data, desired_channels, info)
 }
 
+/// 16-bits-per-channel interface, load image from reader
 pub fn stbi_load_16_from_reader<R>(
     reader: &mut R,
     desired_channels: Channels,
-) -> Option<(Info, Data<u16>)>
+) -> Result<(Info, Data<u16>), ImageError>
 where
     R: io::Read + io::Seek,
 {
     let (mut reader, callbacks) = Wrapper::new(reader);
     let mut info = Info::default();
 
+    apply_thread_settings();
     let data = unsafe {
         sys::stbi_load_16_from_callbacks(
             &callbacks,
@@ -432,24 +1406,23 @@ where
         )
     };
 
-    if data.is_null() {
-        None
-    } else {
-        Some((info, Data::new(data, desired_channels, info)))
-    }
+    load_reader_result(&mut reader, data, desired_channels, info)
 }
 
+/// Floating point interface, load image from memory
 #[cfg(not(feature = "stbi_no_linear"))]
 pub fn stbi_loadf_from_memory(
     buffer: &[u8],
     desired_channels: Channels,
-) -> Option<(Info, Data<f32>)> {
+) -> Result<(Info, Data<f32>), ImageError> {
+    let len = buffer_len(buffer)?;
     let mut info = Info::default();
 
+    apply_thread_settings();
     let data = unsafe {
         sys::stbi_loadf_from_memory(
             buffer.as_ptr(),
-            buffer.len() as i32,
+            len,
             &mut info.width,
             &mut info.height,
             &mut info.components,
@@ -457,24 +1430,22 @@ pub fn stbi_loadf_from_memory(
         )
     };
 
-    if data.is_null() {
-        None
-    } else {
-        Some((info, Data::new(data, desired_channels, info)))
-    }
+    load_result(data, desired_channels, info)
 }
 
+/// Floating point interface, load image from reader
 #[cfg(not(feature = "stbi_no_linear"))]
 pub fn stbi_loadf_from_reader<R>(
     reader: &mut R,
     desired_channels: Channels,
-) -> Option<(Info, Data<f32>)>
+) -> Result<(Info, Data<f32>), ImageError>
 where
     R: io::Read + io::Seek,
 {
     let (mut reader, callbacks) = Wrapper::new(reader);
     let mut info = Info::default();
 
+    apply_thread_settings();
     let data = unsafe {
         sys::stbi_loadf_from_callbacks(
             &callbacks,
@@ -486,77 +1457,786 @@ where
         )
     };
 
-    if data.is_null() {
-        None
+    load_reader_result(&mut reader, data, desired_channels, info)
+}
+
+/// Load all frames of animated GIF from memory along with per-frame delays
+#[cfg(not(feature = "stbi_no_gif"))]
+pub fn stbi_load_gif_from_memory(
+    buffer: &[u8],
+    desired_channels: Channels,
+) -> Result<AnimatedImage, ImageError> {
+    let mut image = AnimatedImage {
+        data: std::ptr::null_mut(),
+        delays: std::ptr::null_mut(),
+        width: 0,
+        height: 0,
+        frames: 0,
+        channels: 0,
+    };
+
+    let len = buffer_len(buffer)?;
+    let mut components = 0;
+    apply_thread_settings();
+    image.data = unsafe {
+        sys::stbi_load_gif_from_memory(
+            buffer.as_ptr(),
+            len,
+            &mut image.delays,
+            &mut image.width,
+            &mut image.height,
+            &mut image.frames,
+            &mut components,
+            desired_channels as i32,
+        )
+    };
+
+    image.channels = if desired_channels == Channels::Default {
+        components
     } else {
-        Some((info, Data::new(data, desired_channels, info)))
+        desired_channels as i32
+    };
+
+    if image.data.is_null() {
+        Err(ImageError::from_failure_reason())
+    } else {
+        Ok(image)
     }
 }
 
-#[cfg(not(feature = "stbi_no_hdr"))]
-pub fn stbi_hdr_to_ldr_gamma(gamma: f32) {
-    unsafe { sys::stbi_hdr_to_ldr_gamma(gamma) }
+/// Returns `BufferTooSmall` if `output` can't hold the image described by `info`
+fn check_output<T>(
+    info: &Info,
+    desired_channels: Channels,
+    output: &[T],
+) -> Result<(), ImageError> {
+    let required = info.size(desired_channels);
+    if output.len() < required {
+        return Err(ImageError::BufferTooSmall {
+            required,
+            actual: output.len(),
+        });
+    }
+    Ok(())
 }
 
-#[cfg(not(feature = "stbi_no_hdr"))]
-pub fn stbi_hdr_to_ldr_scale(scale: f32) {
-    unsafe { sys::stbi_hdr_to_ldr_scale(scale) }
+/// Same as `check_output`, reads image info from reader and rewinds it back
+fn check_reader_output<R, T>(
+    reader: &mut R,
+    desired_channels: Channels,
+    output: &[T],
+) -> Result<(), ImageError>
+where
+    R: io::Read + io::Seek,
+{
+    let start = reader.stream_position()?;
+    let info = ReaderSource { reader, start }.info()?;
+    check_output(&info, desired_channels, output)
+}
+
+/// Copies decoded image into `output`, returns info of the decoded image
+fn copy_into<T: Copy>((info, data): (Info, Data<T>), output: &mut [T]) -> Result<Info, ImageError> {
+    let data = data.as_slice();
+    match output.get_mut(..data.len()) {
+        Some(output) => {
+            output.copy_from_slice(data);
+            Ok(info)
+        }
+        None => Err(ImageError::BufferTooSmall {
+            required: data.len(),
+            actual: output.len(),
+        }),
+    }
 }
 
+/// 8-bits-per-channel interface, decode image from memory and copy it into `output`.
+///
+/// stb can't decode into caller-provided memory: it always allocates the decoded image (and
+/// intermediate buffers) itself, so this decodes the same way as `stbi_load_from_memory` and
+/// copies the pixels into `output`, freeing stb memory right away. It saves keeping `Data`
+/// around when images are decoded into one reusable buffer, not the allocations or the copy.
+///
+/// The header is parsed before decoding, `ImageError::BufferTooSmall` is returned without
+/// decoding if `output` can't hold the image. Use `Info::size` to allocate a reusable buffer.
+pub fn stbi_load_from_memory_copy_into(
+    buffer: &[u8],
+    desired_channels: Channels,
+    output: &mut [u8],
+) -> Result<Info, ImageError> {
+    check_output(&stbi_info_from_memory(buffer)?, desired_channels, output)?;
+    copy_into(stbi_load_from_memory(buffer, desired_channels)?, output)
+}
+
+/// 16-bits-per-channel interface, decode image from memory and copy it into `output`.
+/// See `stbi_load_from_memory_copy_into` for details.
+pub fn stbi_load_16_from_memory_copy_into(
+    buffer: &[u8],
+    desired_channels: Channels,
+    output: &mut [u16],
+) -> Result<Info, ImageError> {
+    check_output(&stbi_info_from_memory(buffer)?, desired_channels, output)?;
+    copy_into(stbi_load_16_from_memory(buffer, desired_channels)?, output)
+}
+
+/// Floating point interface, decode image from memory and copy it into `output`.
+/// See `stbi_load_from_memory_copy_into` for details.
 #[cfg(not(feature = "stbi_no_linear"))]
-pub fn stbi_ldr_to_hdr_gamma(gamma: f32) {
-    unsafe { sys::stbi_ldr_to_hdr_gamma(gamma) }
+pub fn stbi_loadf_from_memory_copy_into(
+    buffer: &[u8],
+    desired_channels: Channels,
+    output: &mut [f32],
+) -> Result<Info, ImageError> {
+    check_output(&stbi_info_from_memory(buffer)?, desired_channels, output)?;
+    copy_into(stbi_loadf_from_memory(buffer, desired_channels)?, output)
+}
+
+/// 8-bits-per-channel interface, decode image from reader and copy it into `output`.
+/// See `stbi_load_from_memory_copy_into` for details.
+pub fn stbi_load_from_reader_copy_into<R>(
+    reader: &mut R,
+    desired_channels: Channels,
+    output: &mut [u8],
+) -> Result<Info, ImageError>
+where
+    R: io::Read + io::Seek,
+{
+    check_reader_output(reader, desired_channels, output)?;
+    copy_into(stbi_load_from_reader(reader, desired_channels)?, output)
+}
+
+/// 16-bits-per-channel interface, decode image from reader and copy it into `output`.
+/// See `stbi_load_from_memory_copy_into` for details.
+pub fn stbi_load_16_from_reader_copy_into<R>(
+    reader: &mut R,
+    desired_channels: Channels,
+    output: &mut [u16],
+) -> Result<Info, ImageError>
+where
+    R: io::Read + io::Seek,
+{
+    check_reader_output(reader, desired_channels, output)?;
+    copy_into(stbi_load_16_from_reader(reader, desired_channels)?, output)
 }
 
+/// Floating point interface, decode image from reader and copy it into `output`.
+/// See `stbi_load_from_memory_copy_into` for details.
 #[cfg(not(feature = "stbi_no_linear"))]
-pub fn stbi_ldr_to_hdr_scale(scale: f32) {
-    unsafe { sys::stbi_ldr_to_hdr_scale(scale) }
+pub fn stbi_loadf_from_reader_copy_into<R>(
+    reader: &mut R,
+    desired_channels: Channels,
+    output: &mut [f32],
+) -> Result<Info, ImageError>
+where
+    R: io::Read + io::Seek,
+{
+    check_reader_output(reader, desired_channels, output)?;
+    copy_into(stbi_loadf_from_reader(reader, desired_channels)?, output)
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
-    use std::path::PathBuf;
+fn open_buffered(path: &Path) -> Result<io::BufReader<fs::File>, ImageError> {
+    Ok(io::BufReader::new(fs::File::open(path)?))
+}
 
-    use super::*;
+/// Get image dimensions & components from file without fully decoding
+pub fn info_path(path: impl AsRef<Path>) -> Result<Info, ImageError> {
+    stbi_info_from_reader(&mut open_buffered(path.as_ref())?)
+}
 
-    fn fixture_path(file: &str) -> PathBuf {
-        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        let mut path = PathBuf::from(root.parent().unwrap());
+/// 8-bits-per-channel interface, load image from file
+pub fn load_path(
+    path: impl AsRef<Path>,
+    desired_channels: Channels,
+) -> Result<(Info, Data<u8>), ImageError> {
+    stbi_load_from_reader(&mut open_buffered(path.as_ref())?, desired_channels)
+}
 
-        path.push("tests/fixtures");
-        path.push(file);
+/// 16-bits-per-channel interface, load image from file
+pub fn load_16_path(
+    path: impl AsRef<Path>,
+    desired_channels: Channels,
+) -> Result<(Info, Data<u16>), ImageError> {
+    stbi_load_16_from_reader(&mut open_buffered(path.as_ref())?, desired_channels)
+}
 
-        path
-    }
+/// Floating point interface, load image from file
+#[cfg(not(feature = "stbi_no_linear"))]
+pub fn loadf_path(
+    path: impl AsRef<Path>,
+    desired_channels: Channels,
+) -> Result<(Info, Data<f32>), ImageError> {
+    stbi_loadf_from_reader(&mut open_buffered(path.as_ref())?, desired_channels)
+}
 
-    #[test]
-    fn info_from_memory() {
-        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+/// Bit depth of decoded image data
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BitDepth {
+    /// 16 bits for 16-bit PNG/PSD/PNM, float for HDR and 8 bits for everything else
+    Auto,
+    /// 8-bits-per-channel, same as `stbi_load_*`
+    #[default]
+    Eight,
+    /// 16-bits-per-channel, same as `stbi_load_16_*`
+    Sixteen,
+    /// Floating point, same as `stbi_loadf_*`
+    #[cfg(not(feature = "stbi_no_linear"))]
+    Float,
+}
 
-        let info = stbi_info_from_memory(&data).expect("Failed to get image info from memory");
-        assert_eq!(info.width, 20);
-        assert_eq!(info.height, 30);
-        assert_eq!(info.components, 1);
+impl BitDepth {
+    /// Size of a single channel component, `Auto` has to be resolved first
+    fn bytes_per_channel(self) -> usize {
+        match self {
+            BitDepth::Auto | BitDepth::Eight => 1,
+            BitDepth::Sixteen => 2,
+            #[cfg(not(feature = "stbi_no_linear"))]
+            BitDepth::Float => 4,
+        }
     }
+}
 
-    #[test]
-    fn info_from_reader() {
-        let mut f = fs::File::open(fixture_path("white.png")).expect("Failed to open file reader");
-        let info = stbi_info_from_reader(&mut f).expect("Failed to get image info from callbacks");
+/// Kind of limit exceeded by an image
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit {
+    Width,
+    Height,
+    Pixels,
+    AllocBytes,
+}
 
-        assert_eq!(info.width, 20);
-        assert_eq!(info.height, 30);
-        assert_eq!(info.components, 1);
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Width => "width",
+            Limit::Height => "height",
+            Limit::Pixels => "pixel count",
+            Limit::AllocBytes => "decoded size",
+        })
     }
+}
 
-    #[test]
-    fn load_8bit_from_memory() {
-        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
-        let (info, image) =
-            stbi_load_from_memory(&data, Channels::Grey).expect("Failed to load image");
+/// Limits for decoding untrusted images.
+///
+/// Limits are checked against the image header before decoding, so images declaring
+/// gigantic dimensions are rejected without allocating memory for them.
+/// `max_alloc_bytes` is compared with the size of decoded pixels, stb may need some extra
+/// memory for decoding on top of that.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum image width in pixels
+    pub max_width: Option<u32>,
+    /// Maximum image height in pixels
+    pub max_height: Option<u32>,
+    /// Maximum number of pixels (width x height)
+    pub max_pixels: Option<u64>,
+    /// Maximum size of decoded image in bytes
+    pub max_alloc_bytes: Option<u64>,
+}
 
-        assert_eq!(info.width, 20);
+impl Limits {
+    /// Checks image `info` against the limits, the decoded image would have `desired_channels`
+    /// (or image components) of `bytes_per_channel` size.
+    pub fn check(
+        &self,
+        info: &Info,
+        desired_channels: Channels,
+        bytes_per_channel: usize,
+    ) -> Result<(), ImageError> {
+        let width = info.width.max(0) as u64;
+        let height = info.height.max(0) as u64;
+        let pixels = width * height;
+        // Sizes overflowing `u64` exceed any limit
+        let bytes = info
+            .checked_size(desired_channels, bytes_per_channel as u64)
+            .unwrap_or(u64::MAX);
+
+        let checks = [
+            (Limit::Width, width, self.max_width.map(u64::from)),
+            (Limit::Height, height, self.max_height.map(u64::from)),
+            (Limit::Pixels, pixels, self.max_pixels),
+            (Limit::AllocBytes, bytes, self.max_alloc_bytes),
+        ];
+
+        for (limit, value, max) in checks.iter().copied() {
+            match max {
+                Some(max) if value > max || value == u64::MAX => {
+                    return Err(ImageError::LimitExceeded { limit, value, max })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rust owned image of the bit depth requested with `LoadOptions`
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicImage {
+    U8(Image<u8>),
+    U16(Image<u16>),
+    #[cfg(not(feature = "stbi_no_linear"))]
+    F32(Image<f32>),
+}
+
+impl DynamicImage {
+    /// Image width in pixels
+    pub fn width(&self) -> i32 {
+        match self {
+            DynamicImage::U8(image) => image.width(),
+            DynamicImage::U16(image) => image.width(),
+            #[cfg(not(feature = "stbi_no_linear"))]
+            DynamicImage::F32(image) => image.width(),
+        }
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> i32 {
+        match self {
+            DynamicImage::U8(image) => image.height(),
+            DynamicImage::U16(image) => image.height(),
+            #[cfg(not(feature = "stbi_no_linear"))]
+            DynamicImage::F32(image) => image.height(),
+        }
+    }
+
+    /// Number of channels per pixel
+    pub fn channels(&self) -> i32 {
+        match self {
+            DynamicImage::U8(image) => image.channels(),
+            DynamicImage::U16(image) => image.channels(),
+            #[cfg(not(feature = "stbi_no_linear"))]
+            DynamicImage::F32(image) => image.channels(),
+        }
+    }
+}
+
+/// Gamma and scale used to convert between LDR and HDR images,
+/// see `stbi_hdr_to_ldr_gamma` and `stbi_ldr_to_hdr_gamma`.
+#[cfg(not(feature = "stbi_no_linear"))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMapping {
+    pub gamma: f32,
+    pub scale: f32,
+}
+
+#[cfg(not(feature = "stbi_no_linear"))]
+impl Default for ToneMapping {
+    /// Same defaults as stb uses: gamma 2.2 and scale 1.0
+    fn default() -> Self {
+        ToneMapping {
+            gamma: 2.2,
+            scale: 1.0,
+        }
+    }
+}
+
+#[cfg(not(feature = "stbi_no_linear"))]
+impl ToneMapping {
+    /// Converts LDR to HDR the same way `stbi__ldr_to_hdr` does, alpha is only normalized
+    fn ldr_to_hdr(&self, image: Image<u8>) -> Image<f32> {
+        let data = map_color_channels(&image, |c, alpha| {
+            let c = c as f32 / 255.0;
+            if alpha {
+                c
+            } else {
+                c.powf(self.gamma) * self.scale
+            }
+        });
+
+        image.with_data(data)
+    }
+
+    /// Converts HDR to LDR the same way `stbi__hdr_to_ldr` does, alpha is only denormalized
+    fn hdr_to_ldr(&self, image: Image<f32>) -> Image<u8> {
+        let data = map_color_channels(&image, |c, alpha| {
+            let c = if alpha {
+                c * 255.0 + 0.5
+            } else {
+                (c / self.scale).powf(1.0 / self.gamma) * 255.0 + 0.5
+            };
+            c.clamp(0.0, 255.0) as u8
+        });
+
+        image.with_data(data)
+    }
+}
+
+/// Maps every channel of the image, the last channel of 2 and 4 channel images is alpha
+#[cfg(not(feature = "stbi_no_linear"))]
+fn map_color_channels<T, U>(image: &Image<T>, f: impl Fn(T, bool) -> U) -> Vec<U>
+where
+    T: Copy,
+{
+    let channels = image.channels as usize;
+    let has_alpha = matches!(channels, 2 | 4);
+
+    image
+        .data
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            f(
+                *c,
+                has_alpha && (i % image.stride) % channels == channels - 1,
+            )
+        })
+        .collect()
+}
+
+/// Per-call load settings.
+///
+/// Unlike `stbi_set_flip_vertically_on_load` and friends, which change process-wide state,
+/// these settings are pinned with `stbi_xyz_thread` variants for the duration of the call,
+/// so loads running concurrently on other threads and changes of global settings don't interfere.
+/// Flipping and iPhone PNG settings replace global ones, the ones not set are off.
+/// stb has no per-thread HDR/LDR conversion settings, so tone mapping is done in Rust instead.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct LoadOptions {
+    desired_channels: Channels,
+    bit_depth: BitDepth,
+    flip_vertically: bool,
+    convert_iphone_png_to_rgb: bool,
+    unpremultiply: bool,
+    limits: Limits,
+    #[cfg(not(feature = "stbi_no_linear"))]
+    hdr_to_ldr: Option<ToneMapping>,
+    #[cfg(not(feature = "stbi_no_linear"))]
+    ldr_to_hdr: Option<ToneMapping>,
+}
+
+impl LoadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of channels to convert the image to, `Channels::Default` keeps image components
+    pub fn desired_channels(mut self, desired_channels: Channels) -> Self {
+        self.desired_channels = desired_channels;
+        self
+    }
+
+    /// Bit depth of decoded data, 8 bits by default
+    pub fn bit_depth(mut self, bit_depth: BitDepth) -> Self {
+        self.bit_depth = bit_depth;
+        self
+    }
+
+    /// Flip the image vertically, so the first pixel in the output array is the bottom left
+    pub fn flip_vertically(mut self, flip: bool) -> Self {
+        self.flip_vertically = flip;
+        self
+    }
+
+    /// Convert iphone-formatted PNGs (BGR stored in RGB) back to RGB
+    pub fn convert_iphone_png_to_rgb(mut self, convert: bool) -> Self {
+        self.convert_iphone_png_to_rgb = convert;
+        self
+    }
+
+    /// Remove premultiplied alpha if the image file explicitly says there's premultiplied data
+    /// (currently only happens in iPhone images, and only if iPhone convert-to-rgb is on)
+    pub fn unpremultiply(mut self, unpremultiply: bool) -> Self {
+        self.unpremultiply = unpremultiply;
+        self
+    }
+
+    /// Gamma and scale used when HDR image is loaded with 8 or 16 bit depth.
+    /// Overrides values set with `stbi_hdr_to_ldr_gamma` and `stbi_hdr_to_ldr_scale`.
+    #[cfg(all(not(feature = "stbi_no_hdr"), not(feature = "stbi_no_linear")))]
+    pub fn hdr_to_ldr(mut self, tone_mapping: ToneMapping) -> Self {
+        self.hdr_to_ldr = Some(tone_mapping);
+        self
+    }
+
+    /// Gamma and scale used when LDR image is loaded with float bit depth.
+    /// Overrides values set with `stbi_ldr_to_hdr_gamma` and `stbi_ldr_to_hdr_scale`.
+    #[cfg(not(feature = "stbi_no_linear"))]
+    pub fn ldr_to_hdr(mut self, tone_mapping: ToneMapping) -> Self {
+        self.ldr_to_hdr = Some(tone_mapping);
+        self
+    }
+
+    /// Reject images exceeding `limits` after reading the header, before decoding
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Load image from memory with these options
+    pub fn load_from_memory(&self, buffer: &[u8]) -> Result<(Info, DynamicImage), ImageError> {
+        self.load(&mut MemorySource(buffer))
+    }
+
+    /// Load image from reader with these options.
+    /// If the image has to be probed first (`BitDepth::Auto` or tone mapping is set),
+    /// the reader is rewound back before decoding.
+    pub fn load_from_reader<R>(&self, reader: &mut R) -> Result<(Info, DynamicImage), ImageError>
+    where
+        R: io::Read + io::Seek,
+    {
+        let start = reader.stream_position()?;
+        self.load(&mut ReaderSource { reader, start })
+    }
+
+    /// Returns true if the source has to be checked for HDR before decoding
+    fn needs_hdr_probe(&self) -> bool {
+        #[cfg(not(feature = "stbi_no_linear"))]
+        if self.hdr_to_ldr.is_some() || self.ldr_to_hdr.is_some() {
+            return true;
+        }
+
+        self.bit_depth == BitDepth::Auto
+    }
+
+    fn load(&self, source: &mut impl Source) -> Result<(Info, DynamicImage), ImageError> {
+        let _settings = PinnedSettings::new(LoadSettings {
+            flip_vertically: self.flip_vertically,
+            convert_iphone_png_to_rgb: self.convert_iphone_png_to_rgb,
+            unpremultiply: self.unpremultiply,
+        });
+
+        let is_hdr = self.needs_hdr_probe() && source.is_hdr()?;
+
+        let bit_depth = match self.bit_depth {
+            BitDepth::Auto if is_hdr => Self::hdr_bit_depth(),
+            BitDepth::Auto if source.is_16_bit()? => BitDepth::Sixteen,
+            BitDepth::Auto => BitDepth::Eight,
+            bit_depth => bit_depth,
+        };
+
+        if self.limits != Limits::default() {
+            let info = source.info()?;
+            self.limits
+                .check(&info, self.desired_channels, bit_depth.bytes_per_channel())?;
+        }
+
+        let channels = self.desired_channels;
+        let (info, image) = match bit_depth {
+            #[cfg(not(feature = "stbi_no_linear"))]
+            BitDepth::Float => match self.ldr_to_hdr {
+                Some(tone_mapping) if !is_hdr => {
+                    let (info, data) = source.load(channels)?;
+                    (
+                        info,
+                        DynamicImage::F32(tone_mapping.ldr_to_hdr(data.into_image())),
+                    )
+                }
+                _ => {
+                    let (info, data) = source.loadf(channels)?;
+                    (info, DynamicImage::F32(data.into_image()))
+                }
+            },
+            BitDepth::Sixteen => match self.load_hdr_as_ldr(source, is_hdr)? {
+                Some((info, image)) => (info, DynamicImage::U16(eight_to_sixteen(image))),
+                None => {
+                    let (info, data) = source.load_16(channels)?;
+                    (info, DynamicImage::U16(data.into_image()))
+                }
+            },
+            _ => match self.load_hdr_as_ldr(source, is_hdr)? {
+                Some((info, image)) => (info, DynamicImage::U8(image)),
+                None => {
+                    let (info, data) = source.load(channels)?;
+                    (info, DynamicImage::U8(data.into_image()))
+                }
+            },
+        };
+
+        Ok((info, image))
+    }
+
+    /// Decodes HDR image as floats and tone maps it in Rust if `hdr_to_ldr` is set
+    #[cfg(not(feature = "stbi_no_linear"))]
+    fn load_hdr_as_ldr(
+        &self,
+        source: &mut impl Source,
+        is_hdr: bool,
+    ) -> Result<Option<(Info, Image<u8>)>, ImageError> {
+        match self.hdr_to_ldr {
+            Some(tone_mapping) if is_hdr => {
+                let (info, data) = source.loadf(self.desired_channels)?;
+                Ok(Some((info, tone_mapping.hdr_to_ldr(data.into_image()))))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[cfg(feature = "stbi_no_linear")]
+    fn load_hdr_as_ldr(
+        &self,
+        _source: &mut impl Source,
+        _is_hdr: bool,
+    ) -> Result<Option<(Info, Image<u8>)>, ImageError> {
+        Ok(None)
+    }
+
+    /// HDR images are decoded as floats unless linear API is disabled
+    fn hdr_bit_depth() -> BitDepth {
+        #[cfg(not(feature = "stbi_no_linear"))]
+        return BitDepth::Float;
+        #[cfg(feature = "stbi_no_linear")]
+        return BitDepth::Eight;
+    }
+}
+
+/// Expands 8 bit image to 16 bits the same way `stbi__convert_8_to_16` does
+fn eight_to_sixteen(image: Image<u8>) -> Image<u16> {
+    let data = image
+        .data
+        .iter()
+        .map(|c| (*c as u16) << 8 | *c as u16)
+        .collect();
+    image.with_data(data)
+}
+
+/// Image source used by `LoadOptions`
+trait Source {
+    fn info(&mut self) -> Result<Info, ImageError>;
+    fn is_hdr(&mut self) -> Result<bool, ImageError>;
+    fn is_16_bit(&mut self) -> Result<bool, ImageError>;
+    fn load(&mut self, channels: Channels) -> Result<(Info, Data<u8>), ImageError>;
+    fn load_16(&mut self, channels: Channels) -> Result<(Info, Data<u16>), ImageError>;
+    #[cfg(not(feature = "stbi_no_linear"))]
+    fn loadf(&mut self, channels: Channels) -> Result<(Info, Data<f32>), ImageError>;
+}
+
+struct MemorySource<'a>(&'a [u8]);
+
+impl Source for MemorySource<'_> {
+    fn info(&mut self) -> Result<Info, ImageError> {
+        stbi_info_from_memory(self.0)
+    }
+
+    fn is_hdr(&mut self) -> Result<bool, ImageError> {
+        stbi_is_hdr_from_memory(self.0)
+    }
+
+    fn is_16_bit(&mut self) -> Result<bool, ImageError> {
+        stbi_is_16_bit_from_memory(self.0)
+    }
+
+    fn load(&mut self, channels: Channels) -> Result<(Info, Data<u8>), ImageError> {
+        stbi_load_from_memory(self.0, channels)
+    }
+
+    fn load_16(&mut self, channels: Channels) -> Result<(Info, Data<u16>), ImageError> {
+        stbi_load_16_from_memory(self.0, channels)
+    }
+
+    #[cfg(not(feature = "stbi_no_linear"))]
+    fn loadf(&mut self, channels: Channels) -> Result<(Info, Data<f32>), ImageError> {
+        stbi_loadf_from_memory(self.0, channels)
+    }
+}
+
+/// Reader source, rewinds the reader to `start` after each probe
+struct ReaderSource<'a, R> {
+    reader: &'a mut R,
+    start: u64,
+}
+
+impl<R> Source for ReaderSource<'_, R>
+where
+    R: io::Read + io::Seek,
+{
+    fn info(&mut self) -> Result<Info, ImageError> {
+        let info = stbi_info_from_reader(self.reader);
+        self.reader.seek(io::SeekFrom::Start(self.start))?;
+        info
+    }
+
+    fn is_hdr(&mut self) -> Result<bool, ImageError> {
+        let is_hdr = stbi_is_hdr_from_reader(self.reader)?;
+        self.reader.seek(io::SeekFrom::Start(self.start))?;
+        Ok(is_hdr)
+    }
+
+    fn is_16_bit(&mut self) -> Result<bool, ImageError> {
+        let is_16_bit = stbi_is_16_bit_from_reader(self.reader)?;
+        self.reader.seek(io::SeekFrom::Start(self.start))?;
+        Ok(is_16_bit)
+    }
+
+    fn load(&mut self, channels: Channels) -> Result<(Info, Data<u8>), ImageError> {
+        stbi_load_from_reader(self.reader, channels)
+    }
+
+    fn load_16(&mut self, channels: Channels) -> Result<(Info, Data<u16>), ImageError> {
+        stbi_load_16_from_reader(self.reader, channels)
+    }
+
+    #[cfg(not(feature = "stbi_no_linear"))]
+    fn loadf(&mut self, channels: Channels) -> Result<(Info, Data<f32>), ImageError> {
+        stbi_loadf_from_reader(self.reader, channels)
+    }
+}
+
+#[cfg(not(feature = "stbi_no_hdr"))]
+pub fn stbi_hdr_to_ldr_gamma(gamma: f32) {
+    unsafe { sys::stbi_hdr_to_ldr_gamma(gamma) }
+}
+
+#[cfg(not(feature = "stbi_no_hdr"))]
+pub fn stbi_hdr_to_ldr_scale(scale: f32) {
+    unsafe { sys::stbi_hdr_to_ldr_scale(scale) }
+}
+
+#[cfg(not(feature = "stbi_no_linear"))]
+pub fn stbi_ldr_to_hdr_gamma(gamma: f32) {
+    unsafe { sys::stbi_ldr_to_hdr_gamma(gamma) }
+}
+
+#[cfg(not(feature = "stbi_no_linear"))]
+pub fn stbi_ldr_to_hdr_scale(scale: f32) {
+    unsafe { sys::stbi_ldr_to_hdr_scale(scale) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Seek;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn fixture_path(file: &str) -> PathBuf {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut path = PathBuf::from(root.parent().unwrap());
+
+        path.push("tests/fixtures");
+        path.push(file);
+
+        path
+    }
+
+    #[test]
+    fn info_from_memory() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+
+        let info = stbi_info_from_memory(&data).expect("Failed to get image info from memory");
+        assert_eq!(info.width, 20);
+        assert_eq!(info.height, 30);
+        assert_eq!(info.components, 1);
+    }
+
+    #[test]
+    fn info_from_reader() {
+        let mut f = fs::File::open(fixture_path("white.png")).expect("Failed to open file reader");
+        let info = stbi_info_from_reader(&mut f).expect("Failed to get image info from callbacks");
+
+        assert_eq!(info.width, 20);
+        assert_eq!(info.height, 30);
+        assert_eq!(info.components, 1);
+    }
+
+    #[test]
+    fn load_8bit_from_memory() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let (info, image) =
+            stbi_load_from_memory(&data, Channels::Grey).expect("Failed to load image");
+
+        assert_eq!(info.width, 20);
         assert_eq!(info.height, 30);
         assert_eq!(info.components, 1);
 
@@ -635,4 +2315,876 @@ mod tests {
             assert_eq!(c, 255);
         }
     }
+
+    #[test]
+    fn into_image() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let (_, mut data) =
+            stbi_load_from_memory(&data, Channels::GreyAlpha).expect("Failed to load image");
+
+        data.as_mut_slice()[0] = 0;
+
+        let image = data.into_image();
+        assert_eq!(image.width(), 20);
+        assert_eq!(image.height(), 30);
+        assert_eq!(image.channels(), 2);
+        assert_eq!(image.stride(), 40);
+        assert_eq!(image.pixel(0, 0), &[0, 255]);
+        assert_eq!(image.pixel(19, 29), &[255, 255]);
+    }
+
+    #[test]
+    fn image_from_vec() {
+        assert!(Image::from_vec(2, 2, 1, vec![0_u8; 4]).is_some());
+        assert!(Image::from_vec(2, 2, 1, vec![0_u8; 3]).is_none());
+        assert!(Image::from_vec(0, 2, 1, vec![0_u8; 4]).is_none());
+        assert!(Image::from_vec_with_stride(2, 2, 1, 1, vec![0_u8; 4]).is_none());
+
+        let image = Image::from_vec_with_stride(2, 2, 1, 3, vec![1_u8, 2, 0, 3, 4, 0])
+            .expect("Failed to create image");
+        assert_eq!(image.row(1), &[3, 4]);
+    }
+
+    #[test]
+    fn image_flip_vertically() {
+        let mut image = Image::from_vec_with_stride(1, 3, 2, 3, vec![1_u8, 1, 0, 2, 2, 0, 3, 3, 0])
+            .expect("Failed to create image");
+
+        image.flip_vertically();
+        assert_eq!(image.as_slice(), &[3, 3, 0, 2, 2, 0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn image_convert_channels() {
+        let image = Image::from_vec(2, 1, 3, vec![255_u8, 255, 255, 0, 0, 255])
+            .expect("Failed to create image");
+
+        let rgba = image
+            .convert_channels(Channels::RgbAlpha)
+            .expect("Failed to convert");
+        assert_eq!(rgba.as_slice(), &[255, 255, 255, 255, 0, 0, 255, 255]);
+
+        let grey = image
+            .convert_channels(Channels::Grey)
+            .expect("Failed to convert");
+        assert_eq!(grey.as_slice(), &[255, 28]);
+
+        let grey = Image::from_vec(1, 1, 2, vec![0.5_f32, 0.25]).expect("Failed to create image");
+        let rgb = grey
+            .convert_channels(Channels::Rgb)
+            .expect("Failed to convert");
+        assert_eq!(rgb.as_slice(), &[0.5, 0.5, 0.5]);
+
+        let image = Image::from_vec(1, 1, 5, vec![0_u16; 5]).expect("Failed to create image");
+        assert!(image.convert_channels(Channels::Grey).is_none());
+    }
+
+    #[cfg(feature = "stb_image_resize")]
+    #[test]
+    fn image_resize() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let (_, data) =
+            stbi_load_16_from_memory(&data, Channels::Grey).expect("Failed to load image");
+
+        let image = data
+            .into_image()
+            .resize(40, 15)
+            .expect("Failed to resize image");
+        assert_eq!(image.width(), 40);
+        assert_eq!(image.height(), 15);
+        assert_eq!(image.as_slice().len(), 600);
+        assert!(image.as_slice().iter().all(|c| *c == u16::MAX));
+    }
+
+    #[test]
+    fn load_unknown_format() {
+        let err = stbi_load_from_memory(b"not an image", Channels::Default)
+            .err()
+            .expect("Loaded invalid image");
+        assert!(matches!(err, ImageError::UnknownFormat));
+
+        let err = stbi_info_from_memory(b"not an image").expect_err("Got info of invalid image");
+        assert!(matches!(err, ImageError::UnknownFormat));
+    }
+
+    #[test]
+    fn load_corrupt_png() {
+        let mut data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        data.truncate(40);
+
+        let err = stbi_load_from_memory(&data, Channels::Default)
+            .err()
+            .expect("Loaded corrupt image");
+        assert!(matches!(err, ImageError::Decode(_)), "{:?}", err);
+    }
+
+    struct FailingReader;
+
+    impl io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk read failed"))
+        }
+    }
+
+    impl io::Seek for FailingReader {
+        fn seek(&mut self, _: io::SeekFrom) -> io::Result<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn load_reader_error() {
+        let err = stbi_load_from_reader(&mut FailingReader, Channels::Default)
+            .err()
+            .expect("Loaded image from failing reader");
+
+        match err {
+            ImageError::Io(err) => assert_eq!(err.to_string(), "disk read failed"),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn memory_input_too_large() {
+        // Zeroed memory is mapped lazily and never touched, length is checked first
+        let buffer = vec![0_u8; raw::c_int::MAX as usize + 1];
+        let len = buffer.len();
+
+        let too_large =
+            |err: ImageError| matches!(err, ImageError::InputTooLarge { len: l } if l == len);
+        assert!(too_large(stbi_info_from_memory(&buffer).unwrap_err()));
+        assert!(too_large(stbi_is_16_bit_from_memory(&buffer).unwrap_err()));
+        assert!(too_large(stbi_is_hdr_from_memory(&buffer).unwrap_err()));
+        assert!(too_large(probe(&buffer).unwrap_err()));
+        assert!(too_large(
+            stbi_load_from_memory(&buffer, Channels::Default)
+                .err()
+                .unwrap()
+        ));
+        assert!(too_large(
+            stbi_load_16_from_memory(&buffer, Channels::Default)
+                .err()
+                .unwrap()
+        ));
+        #[cfg(not(feature = "stbi_no_linear"))]
+        assert!(too_large(
+            stbi_loadf_from_memory(&buffer, Channels::Default)
+                .err()
+                .unwrap()
+        ));
+        #[cfg(not(feature = "stbi_no_gif"))]
+        assert!(too_large(
+            stbi_load_gif_from_memory(&buffer, Channels::Default)
+                .err()
+                .unwrap()
+        ));
+    }
+
+    #[test]
+    fn info_reader_error() {
+        let err =
+            stbi_info_from_reader(&mut FailingReader).expect_err("Got info from failing reader");
+        assert!(matches!(err, ImageError::Io(_)));
+
+        assert!(stbi_is_16_bit_from_reader(&mut FailingReader).is_err());
+    }
+
+    /// Returns at most one byte per read and interrupts every other call
+    struct TrickleReader {
+        inner: io::Cursor<Vec<u8>>,
+        interrupt: bool,
+    }
+
+    impl io::Read for TrickleReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+            }
+
+            let len = buf.len().min(1);
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    impl io::Seek for TrickleReader {
+        fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn load_from_short_reads() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let mut reader = TrickleReader {
+            inner: io::Cursor::new(data),
+            interrupt: false,
+        };
+
+        let (info, image) =
+            stbi_load_from_reader(&mut reader, Channels::Grey).expect("Failed to load image");
+        assert_eq!(info.width, 20);
+        assert_eq!(info.height, 30);
+        assert!(image.as_slice().iter().all(|c| *c == 255));
+    }
+
+    #[test]
+    fn load_truncated_reader() {
+        let mut data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        data.truncate(40);
+
+        // Running out of data is not an IO error, stb reports what's wrong with the image
+        let err = stbi_load_from_reader(&mut io::Cursor::new(data), Channels::Default)
+            .err()
+            .expect("Loaded truncated image");
+        assert!(matches!(err, ImageError::Decode(_)), "{:?}", err);
+    }
+
+    #[cfg(not(feature = "stbi_no_gif"))]
+    #[test]
+    fn load_animated_gif() {
+        let data = fs::read(fixture_path("animated.gif")).expect("Failed to read test file");
+        let image =
+            stbi_load_gif_from_memory(&data, Channels::Default).expect("Failed to load GIF");
+
+        assert_eq!(image.width(), 2);
+        assert_eq!(image.height(), 2);
+        assert_eq!(image.channels(), 4);
+        assert_eq!(image.frame_count(), 2);
+        assert_eq!(image.delays(), &[100, 250]);
+
+        let frames = image.frames().collect::<Vec<_>>();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].iter().all(|c| *c == 255));
+        assert_eq!(frames[1], &[0, 0, 0, 255].repeat(4)[..]);
+        assert!(image.frame(2).is_none());
+    }
+
+    #[cfg(not(feature = "stbi_no_gif"))]
+    #[test]
+    fn load_animated_gif_remap_channels() {
+        let data = fs::read(fixture_path("animated.gif")).expect("Failed to read test file");
+        let image = stbi_load_gif_from_memory(&data, Channels::Grey).expect("Failed to load GIF");
+
+        assert_eq!(image.channels(), 1);
+        assert_eq!(image.frame_size(), 4);
+        assert_eq!(image.frame(0), Some(&[255, 255, 255, 255][..]));
+        assert_eq!(image.frame(1), Some(&[0, 0, 0, 0][..]));
+        assert_eq!(image.delay(1), Some(250));
+    }
+
+    #[cfg(not(feature = "stbi_no_gif"))]
+    #[test]
+    fn animated_invalid_size() {
+        let image = AnimatedImage {
+            data: std::ptr::null_mut(),
+            delays: std::ptr::null_mut(),
+            width: -1,
+            height: 10,
+            frames: 2,
+            channels: 4,
+        };
+        assert_eq!(image.frame_size(), 0);
+        assert!(image.as_slice().is_empty());
+        assert!(image.frame(0).is_none());
+    }
+
+    #[test]
+    fn load_animated_not_gif() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        assert!(stbi_load_gif_from_memory(&data, Channels::Default).is_err());
+    }
+
+    #[test]
+    fn load_options_flip() {
+        let data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+
+        let (_, image) = LoadOptions::new()
+            .flip_vertically(true)
+            .load_from_memory(&data)
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::U8(image) => assert_eq!(image.as_slice(), &[255, 255, 0, 0]),
+            _ => panic!("Unexpected bit depth"),
+        }
+
+        let (_, image) = LoadOptions::new()
+            .load_from_memory(&data)
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::U8(image) => assert_eq!(image.as_slice(), &[0, 0, 255, 255]),
+            _ => panic!("Unexpected bit depth"),
+        }
+    }
+
+    #[test]
+    fn load_options_flip_not_leaked() {
+        let data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+
+        LoadOptions::new()
+            .flip_vertically(true)
+            .load_from_memory(&data)
+            .expect("Failed to load image");
+
+        let (_, data) =
+            stbi_load_from_memory(&data, Channels::Default).expect("Failed to load image");
+        assert_eq!(data.as_slice(), &[0, 0, 255, 255]);
+    }
+
+    /// Builds 1x1 RGBA iPhone PNG: `CgBI` chunk, raw deflate stream and no CRCs (stb skips them)
+    fn iphone_png(bgra: [u8; 4]) -> Vec<u8> {
+        fn chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            png.extend_from_slice(kind);
+            png.extend_from_slice(data);
+            png.extend_from_slice(&[0; 4]);
+        }
+
+        let mut png = vec![137, 80, 78, 71, 13, 10, 26, 10];
+        chunk(&mut png, b"CgBI", &[0x50, 0x00, 0x20, 0x06]);
+        chunk(&mut png, b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+        // Single stored deflate block with the filter byte and the pixel
+        let [b, g, r, a] = bgra;
+        chunk(&mut png, b"IDAT", &[1, 5, 0, 0xfa, 0xff, 0, b, g, r, a]);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn load_options_iphone_png() {
+        let data = iphone_png([50, 100, 25, 128]);
+
+        let load = |options: LoadOptions| match options.load_from_memory(&data) {
+            Ok((_, DynamicImage::U8(image))) => image.as_slice().to_vec(),
+            _ => panic!("Failed to load image"),
+        };
+
+        assert_eq!(load(LoadOptions::new()), vec![50, 100, 25, 128]);
+        let options = LoadOptions::new().convert_iphone_png_to_rgb(true);
+        assert_eq!(load(options), vec![25, 100, 50, 128]);
+        assert_eq!(load(options.unpremultiply(true)), vec![50, 199, 100, 128]);
+        assert_eq!(
+            load(options.unpremultiply(true).desired_channels(Channels::Rgb)),
+            vec![50, 199, 100]
+        );
+
+        let mut reader = io::Cursor::new(&data);
+        match options.load_from_reader(&mut reader) {
+            Ok((_, DynamicImage::U8(image))) => assert_eq!(image.as_slice(), &[25, 100, 50, 128]),
+            _ => panic!("Failed to load image"),
+        }
+    }
+
+    #[test]
+    fn load_options_global_settings_changed() {
+        let data = iphone_png([50, 100, 25, 128]);
+
+        // Other tests don't load iPhone PNGs without `LoadOptions`, so toggling these is safe
+        let stop = Arc::new(AtomicBool::new(false));
+        let toggle = {
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                let mut enable = true;
+                while !stop.load(Ordering::Relaxed) {
+                    stbi_convert_iphone_png_to_rgb(enable);
+                    stbi_set_unpremultiply_on_load(enable);
+                    enable = !enable;
+                }
+                stbi_convert_iphone_png_to_rgb(false);
+                stbi_set_unpremultiply_on_load(false);
+            })
+        };
+
+        let load = |options: LoadOptions| match options.load_from_memory(&data) {
+            Ok((_, DynamicImage::U8(image))) => image.as_slice().to_vec(),
+            _ => panic!("Failed to load image"),
+        };
+
+        for _ in 0..1000 {
+            assert_eq!(load(LoadOptions::new()), vec![50, 100, 25, 128]);
+            assert_eq!(
+                load(LoadOptions::new().convert_iphone_png_to_rgb(true)),
+                vec![25, 100, 50, 128]
+            );
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        toggle.join().expect("Toggle thread failed");
+
+        // Global settings still apply to loads without options on this thread
+        stbi_convert_iphone_png_to_rgb(true);
+        let result = stbi_load_from_memory(&data, Channels::Default);
+        stbi_convert_iphone_png_to_rgb(false);
+        let (_, converted) = result.expect("Failed to load image");
+        assert_eq!(converted.as_slice(), &[25, 100, 50, 128]);
+    }
+
+    #[test]
+    fn load_options_bit_depth() {
+        let mut f = fs::File::open(fixture_path("white.png")).expect("Failed to open file reader");
+
+        let (info, image) = LoadOptions::new()
+            .bit_depth(BitDepth::Auto)
+            .desired_channels(Channels::Rgb)
+            .load_from_reader(&mut f)
+            .expect("Failed to load image");
+        assert_eq!(info.components, 1);
+        assert_eq!(image.channels(), 3);
+        assert!(matches!(image, DynamicImage::U8(_)));
+
+        f.rewind().expect("Failed to rewind");
+        let (_, image) = LoadOptions::new()
+            .bit_depth(BitDepth::Sixteen)
+            .load_from_reader(&mut f)
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::U16(image) => assert!(image.as_slice().iter().all(|c| *c == u16::MAX)),
+            _ => panic!("Unexpected bit depth"),
+        }
+    }
+
+    #[test]
+    fn load_options_concurrent() {
+        let data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+
+        let threads = (0..4)
+            .map(|i| {
+                let data = data.clone();
+                std::thread::spawn(move || {
+                    let flip = i % 2 == 0;
+                    let expected: &[u8] = if flip {
+                        &[255, 255, 0, 0]
+                    } else {
+                        &[0, 0, 255, 255]
+                    };
+
+                    for _ in 0..100 {
+                        let (_, image) = LoadOptions::new()
+                            .flip_vertically(flip)
+                            .load_from_memory(&data)
+                            .expect("Failed to load image");
+
+                        match image {
+                            DynamicImage::U8(image) => assert_eq!(image.as_slice(), expected),
+                            _ => panic!("Unexpected bit depth"),
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("Load thread failed");
+        }
+    }
+
+    #[cfg(not(any(feature = "stbi_no_hdr", feature = "stbi_no_linear")))]
+    #[test]
+    fn load_options_hdr_to_ldr() {
+        let data = fs::read(fixture_path("gradient.hdr")).expect("Failed to read test file");
+
+        let (_, image) = LoadOptions::new()
+            .bit_depth(BitDepth::Auto)
+            .load_from_memory(&data)
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::F32(image) => {
+                assert_eq!(image.as_slice(), &[1.0, 1.0, 1.0, 0.25, 0.25, 0.25])
+            }
+            _ => panic!("Unexpected bit depth"),
+        }
+
+        let linear = ToneMapping {
+            gamma: 1.0,
+            scale: 1.0,
+        };
+        let (_, image) = LoadOptions::new()
+            .hdr_to_ldr(linear)
+            .desired_channels(Channels::Grey)
+            .load_from_memory(&data)
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::U8(image) => assert_eq!(image.as_slice(), &[255, 64]),
+            _ => panic!("Unexpected bit depth"),
+        }
+
+        let gamma = ToneMapping {
+            gamma: 2.0,
+            scale: 1.0,
+        };
+        let (_, image) = LoadOptions::new()
+            .hdr_to_ldr(gamma)
+            .bit_depth(BitDepth::Sixteen)
+            .desired_channels(Channels::GreyAlpha)
+            .load_from_reader(&mut io::Cursor::new(data))
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::U16(image) => assert_eq!(image.as_slice(), &[65535, 65535, 32896, 65535]),
+            _ => panic!("Unexpected bit depth"),
+        }
+    }
+
+    #[cfg(not(feature = "stbi_no_linear"))]
+    #[test]
+    fn load_options_ldr_to_hdr() {
+        let data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+
+        let tone_mapping = ToneMapping {
+            gamma: 1.0,
+            scale: 2.0,
+        };
+        let (_, image) = LoadOptions::new()
+            .ldr_to_hdr(tone_mapping)
+            .bit_depth(BitDepth::Float)
+            .desired_channels(Channels::GreyAlpha)
+            .load_from_memory(&data)
+            .expect("Failed to load image");
+        match image {
+            DynamicImage::F32(image) => {
+                assert_eq!(image.as_slice(), &[0.0, 1.0, 0.0, 1.0, 2.0, 1.0, 2.0, 1.0])
+            }
+            _ => panic!("Unexpected bit depth"),
+        }
+    }
+
+    #[test]
+    fn detect_format_from_memory() {
+        let png = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let gif = fs::read(fixture_path("animated.gif")).expect("Failed to read test file");
+        let hdr = fs::read(fixture_path("gradient.hdr")).expect("Failed to read test file");
+
+        assert_eq!(detect_format(&png), Some(ImageFormat::Png));
+        assert_eq!(detect_format(&gif), Some(ImageFormat::Gif));
+        assert_eq!(detect_format(&hdr), Some(ImageFormat::Hdr));
+        assert_eq!(detect_format(b"\xFF\xD8\xFF\xE0"), Some(ImageFormat::Jpeg));
+        assert_eq!(detect_format(b"8BPS\x00\x01"), Some(ImageFormat::Psd));
+        assert_eq!(detect_format(b"P6\n2 2\n255\n"), Some(ImageFormat::Pnm));
+
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&[0; 12]);
+        bmp.extend_from_slice(&40_u32.to_le_bytes());
+        assert_eq!(detect_format(&bmp), Some(ImageFormat::Bmp));
+
+        // 2x2 uncompressed 24-bit TGA header
+        let tga = [0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 2, 0, 24, 0];
+        assert_eq!(detect_format(&tga), Some(ImageFormat::Tga));
+
+        let mut pic = vec![0x53, 0x80, 0xF6, 0x34];
+        pic.resize(88, 0);
+        pic.extend_from_slice(b"PICT");
+        assert_eq!(detect_format(&pic), Some(ImageFormat::Pic));
+
+        assert_eq!(detect_format(b"not an image"), None);
+        assert_eq!(detect_format(&[]), None);
+    }
+
+    #[test]
+    fn detect_format_rewinds_reader() {
+        let mut f = fs::File::open(fixture_path("white.png")).expect("Failed to open file reader");
+
+        let format = detect_format_from_reader(&mut f).expect("Failed to read file");
+        assert_eq!(format, Some(ImageFormat::Png));
+        assert_eq!(f.stream_position().expect("Failed to get position"), 0);
+
+        stbi_load_from_reader(&mut f, Channels::Default).expect("Failed to load image");
+    }
+
+    #[test]
+    fn probe_from_memory() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let info = probe(&data).expect("Failed to probe image");
+
+        assert_eq!(info.width, 20);
+        assert_eq!(info.height, 30);
+        assert_eq!(info.components, 1);
+        assert!(!info.is_16_bit);
+        assert!(!info.is_hdr);
+        assert_eq!(info.format, Some(ImageFormat::Png));
+
+        assert!(matches!(
+            probe(b"not an image"),
+            Err(ImageError::UnknownFormat)
+        ));
+    }
+
+    #[cfg(not(feature = "stbi_no_hdr"))]
+    #[test]
+    fn probe_reader_rewinds() {
+        let data = fs::read(fixture_path("gradient.hdr")).expect("Failed to read test file");
+        let mut reader = io::Cursor::new(data);
+
+        let info = probe_from_reader(&mut reader).expect("Failed to probe image");
+        assert_eq!(info.width, 2);
+        assert_eq!(info.height, 1);
+        assert_eq!(info.components, 3);
+        assert!(info.is_hdr);
+        assert_eq!(info.format, Some(ImageFormat::Hdr));
+        assert_eq!(reader.position(), 0);
+
+        stbi_load_from_reader(&mut reader, Channels::Default).expect("Failed to load image");
+    }
+
+    #[test]
+    fn load_from_path() {
+        let path = fixture_path("white.png");
+
+        let info = info_path(&path).expect("Failed to get image info from path");
+        assert_eq!(info.width, 20);
+        assert_eq!(info.height, 30);
+
+        let (_, image) = load_path(&path, Channels::Grey).expect("Failed to load image");
+        assert!(image.as_slice().iter().all(|c| *c == 255));
+
+        let (_, image) = load_16_path(&path, Channels::Grey).expect("Failed to load image");
+        assert!(image.as_slice().iter().all(|c| *c == u16::MAX));
+
+        #[cfg(not(feature = "stbi_no_linear"))]
+        {
+            let (_, image) = loadf_path(&path, Channels::Grey).expect("Failed to load image");
+            assert!(image.as_slice().iter().all(|c| *c == 1.0));
+        }
+    }
+
+    #[test]
+    fn load_from_missing_path() {
+        match info_path(fixture_path("missing.png")) {
+            Err(ImageError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    /// Reader without `Seek` implementation, like pipes and sockets
+    struct Stream(io::Cursor<Vec<u8>>);
+
+    impl io::Read for Stream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn load_from_stream() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+
+        let info = stbi_info_from_stream(&mut Stream(io::Cursor::new(data.clone())))
+            .expect("Failed to get image info from stream");
+        assert_eq!(info.width, 20);
+        assert_eq!(info.height, 30);
+
+        let (_, image) = stbi_load_from_stream(&mut Stream(io::Cursor::new(data)), Channels::Grey)
+            .expect("Failed to load image from stream");
+        assert!(image.as_slice().iter().all(|c| *c == 255));
+    }
+
+    #[test]
+    fn lookback_seek() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let mut reader = Lookback::new(Stream(io::Cursor::new(data)));
+
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf).expect("Failed to read");
+        assert_eq!(buf, [0, 1, 2, 3]);
+
+        // Skip forward, then unget a few bytes across the skipped range
+        assert_eq!(reader.seek(io::SeekFrom::Current(4)).unwrap(), 8);
+        assert_eq!(reader.seek(io::SeekFrom::Current(-6)).unwrap(), 2);
+        reader.read_exact(&mut buf).expect("Failed to read");
+        assert_eq!(buf, [2, 3, 4, 5]);
+
+        assert!(reader.seek(io::SeekFrom::Current(-7)).is_err());
+        assert!(reader.seek(io::SeekFrom::Start(0)).is_err());
+        assert_eq!(reader.stream_position().unwrap(), 6);
+    }
+
+    #[test]
+    fn load_copy_into_buffer() {
+        let data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+
+        let info = stbi_info_from_memory(&data).expect("Failed to get image info");
+        let mut output = vec![0_u8; info.size(Channels::GreyAlpha)];
+
+        for _ in 0..2 {
+            let info = stbi_load_from_memory_copy_into(&data, Channels::GreyAlpha, &mut output)
+                .expect("Failed to decode image");
+            assert_eq!(info.width, 2);
+            assert_eq!(output, [0, 255, 0, 255, 255, 255, 255, 255]);
+        }
+
+        let mut output = [0_u16; 4];
+        stbi_load_16_from_memory_copy_into(&data, Channels::Default, &mut output)
+            .expect("Failed to decode image");
+        assert_eq!(output, [0, 0, u16::MAX, u16::MAX]);
+    }
+
+    #[test]
+    fn load_copy_into_buffer_from_reader() {
+        let mut f = fs::File::open(fixture_path("black_white.png")).expect("Failed to open file");
+
+        let mut output = [0_u8; 4];
+        let info = stbi_load_from_reader_copy_into(&mut f, Channels::Default, &mut output)
+            .expect("Failed to decode image");
+        assert_eq!(info.height, 2);
+        assert_eq!(output, [0, 0, 255, 255]);
+
+        f.rewind().expect("Failed to rewind");
+        let mut output = [0_u16; 4];
+        stbi_load_16_from_reader_copy_into(&mut f, Channels::Default, &mut output)
+            .expect("Failed to decode image");
+        assert_eq!(output, [0, 0, u16::MAX, u16::MAX]);
+    }
+
+    #[test]
+    fn load_copy_into_small_buffer() {
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+
+        let mut output = [0_u8; 600];
+        match stbi_load_from_memory_copy_into(&data, Channels::Rgb, &mut output) {
+            Err(ImageError::BufferTooSmall { required, actual }) => {
+                assert_eq!(required, 1800);
+                assert_eq!(actual, 600);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        // Rejected after parsing the header, there is no pixel data to decode
+        let data = huge_png();
+        match stbi_load_from_memory_copy_into(&data, Channels::Rgb, &mut output) {
+            Err(ImageError::BufferTooSmall { required, .. }) => assert_eq!(required, 300_000_000),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let mut reader = io::Cursor::new(data);
+        match stbi_load_from_reader_copy_into(&mut reader, Channels::Rgb, &mut output) {
+            Err(ImageError::BufferTooSmall { required, .. }) => assert_eq!(required, 300_000_000),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert_eq!(reader.position(), 0);
+    }
+
+    /// PNG header declaring 10000 x 10000 image without any pixel data
+    fn huge_png() -> Vec<u8> {
+        let mut data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+        data[16..20].copy_from_slice(&10_000_u32.to_be_bytes());
+        data[20..24].copy_from_slice(&10_000_u32.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn load_options_limits() {
+        let limits = Limits {
+            max_pixels: Some(4096 * 4096),
+            ..Limits::default()
+        };
+
+        let err = LoadOptions::new()
+            .limits(limits)
+            .load_from_reader(&mut io::Cursor::new(huge_png()))
+            .expect_err("Loaded image exceeding limits");
+        match err {
+            ImageError::LimitExceeded { limit, value, max } => {
+                assert_eq!(limit, Limit::Pixels);
+                assert_eq!(value, 100_000_000);
+                assert_eq!(max, 4096 * 4096);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let data = fs::read(fixture_path("white.png")).expect("Failed to read test file");
+        let options = LoadOptions::new().bit_depth(BitDepth::Sixteen);
+
+        let limits = Limits {
+            max_width: Some(20),
+            max_height: Some(30),
+            max_alloc_bytes: Some(1200),
+            ..Limits::default()
+        };
+        options
+            .limits(limits)
+            .load_from_memory(&data)
+            .expect("Failed to load image within limits");
+
+        let limits = Limits {
+            max_alloc_bytes: Some(1199),
+            ..Limits::default()
+        };
+        let err = options
+            .limits(limits)
+            .load_from_memory(&data)
+            .expect_err("Loaded image exceeding limits");
+        assert!(matches!(
+            err,
+            ImageError::LimitExceeded {
+                limit: Limit::AllocBytes,
+                ..
+            }
+        ));
+    }
+
+    /// Builds header of HDR image declaring the given dimensions, stb doesn't limit them
+    #[cfg(not(any(feature = "stbi_no_hdr", feature = "stbi_no_linear")))]
+    fn huge_hdr(width: u32, height: u32) -> Vec<u8> {
+        format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            height, width
+        )
+        .into_bytes()
+    }
+
+    #[cfg(not(any(feature = "stbi_no_hdr", feature = "stbi_no_linear")))]
+    #[test]
+    fn load_options_limits_overflow() {
+        let limits = Limits {
+            max_alloc_bytes: Some(1 << 30),
+            ..Limits::default()
+        };
+        let options = LoadOptions::new().bit_depth(BitDepth::Float).limits(limits);
+
+        // Decoded size overflows `u32`, so `usize` on 32-bit targets
+        let err = options
+            .load_from_memory(&huge_hdr(65536, 65536))
+            .expect_err("Loaded image exceeding limits");
+        match err {
+            ImageError::LimitExceeded { limit, value, max } => {
+                assert_eq!(limit, Limit::AllocBytes);
+                assert_eq!(value, 65536 * 65536 * 3 * 4);
+                assert_eq!(max, 1 << 30);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Decoded size overflows `u64`
+        let max = i32::MAX as u32;
+        let err = options
+            .limits(Limits {
+                max_alloc_bytes: Some(u64::MAX),
+                ..Limits::default()
+            })
+            .load_from_memory(&huge_hdr(max, max))
+            .expect_err("Loaded image exceeding limits");
+        match err {
+            ImageError::LimitExceeded { limit, value, .. } => {
+                assert_eq!(limit, Limit::AllocBytes);
+                assert_eq!(value, u64::MAX);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn image_views() {
+        let data = fs::read(fixture_path("black_white.png")).expect("Failed to read test file");
+        let (_, data) = stbi_load_from_memory(&data, Channels::Rgb).expect("Failed to load image");
+
+        let view = data.view().expect("Failed to get image view");
+        assert_eq!(data.size(), 12);
+        assert_eq!((view.width(), view.height(), view.channels()), (2, 2, 3));
+        assert_eq!(view.pixel(1, 0), &[0, 0, 0]);
+        assert_eq!(view.pixel(1, 1), &[255, 255, 255]);
+
+        let image = Image::from_vec_with_stride(1, 2, 1, 3, vec![1_u8, 9, 9, 2, 9, 9]).unwrap();
+        let view = image.view();
+        assert_eq!(view.stride(), 3);
+        assert_eq!(view.to_packed().as_ref(), &[1, 2]);
+    }
 }
@@ -0,0 +1,468 @@
+//! Rust API for image resizing, see https://github.com/nothings/stb/blob/master/stb_image_resize.h
+//!
+//! Written with emphasis on usability, portability, and efficiency. (No
+//! SIMD or threads, so it be easily outperformed by libs that use those.)
+//! Only scaling and translation is supported, no rotations or shears.
+//! Easy API downsamples w/Mitchell filter, upsamples w/cubic interpolation.
+//!
+//! Rust implementation notes:
+//!
+//! - Functions operate on plain slices, 0 stride means the image rows are tightly packed.
+//!   With explicit stride slices don't need padding after the last row.
+//! - Slice sizes, strides and channels are validated before calling stb, functions return `None`
+//!   if any of them doesn't match the image dimensions or if stb fails to resize the image.
+//! - `stbir_resize` is the full-featured API working with `u8`, `u16` and `f32` components.
+//! - `resize` takes an `ImageView` and returns tightly packed pixels of the resized image.
+
+use std::convert::TryFrom;
+use std::mem::size_of;
+use std::os::raw;
+use std::ptr;
+
+use stb_sys as sys;
+
+use crate::image_view::ImageView;
+
+/// Maximum number of channels supported by stb (`STBIR_MAX_CHANNELS`)
+const MAX_CHANNELS: usize = 64;
+
+/// Image edge handling
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Edge {
+    #[default]
+    Clamp = 1,
+    Reflect = 2,
+    Wrap = 3,
+    Zero = 4,
+}
+
+/// Filter used for resampling
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Use same filter type that easy-to-use API chooses
+    #[default]
+    Default = 0,
+    /// A trapezoid w/1-pixel wide ramps, same result as box for integer scale ratios
+    Box = 1,
+    /// On upsampling, produces same results as bilinear texture filtering
+    Triangle = 2,
+    /// The cubic b-spline (aka Mitchell-Netrevalli with B=1,C=0), gaussian-esque
+    CubicBSpline = 3,
+    /// An interpolating cubic spline
+    CatmullRom = 4,
+    /// Mitchell-Netrevalli filter with B=1/3, C=1/3
+    Mitchell = 5,
+}
+
+/// Color space of the image data
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ColorSpace {
+    #[default]
+    Linear = 0,
+    Srgb = 1,
+}
+
+/// Full set of resizing options used by `stbir_resize`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Index of the alpha channel, if any.
+    /// Color channels are weighted by alpha, and alpha is not gamma corrected.
+    pub alpha_channel: Option<usize>,
+    /// Set if the color channels are already premultiplied by alpha,
+    /// so no extra premultiplication is done
+    pub alpha_premultiplied: bool,
+    /// Set to gamma correct the alpha channel too when using `ColorSpace::Srgb`
+    pub alpha_uses_colorspace: bool,
+    /// Edge handling along X axis
+    pub edge_horizontal: Edge,
+    /// Edge handling along Y axis
+    pub edge_vertical: Edge,
+    /// Filter along X axis
+    pub filter_horizontal: Filter,
+    /// Filter along Y axis
+    pub filter_vertical: Filter,
+    /// Color space of the color channels
+    pub colorspace: ColorSpace,
+}
+
+impl Options {
+    /// Uses the same edge mode along both axes
+    pub fn with_edge(mut self, edge: Edge) -> Self {
+        self.edge_horizontal = edge;
+        self.edge_vertical = edge;
+        self
+    }
+
+    /// Uses the same filter along both axes
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter_horizontal = filter;
+        self.filter_vertical = filter;
+        self
+    }
+
+    fn flags(&self) -> raw::c_int {
+        let mut flags = 0;
+        if self.alpha_premultiplied {
+            flags |= FLAG_ALPHA_PREMULTIPLIED;
+        }
+        if self.alpha_uses_colorspace {
+            flags |= FLAG_ALPHA_USES_COLORSPACE;
+        }
+        flags
+    }
+}
+
+/// `STBIR_FLAG_ALPHA_PREMULTIPLIED`
+const FLAG_ALPHA_PREMULTIPLIED: raw::c_int = 1;
+/// `STBIR_FLAG_ALPHA_USES_COLORSPACE`
+const FLAG_ALPHA_USES_COLORSPACE: raw::c_int = 1 << 1;
+/// `STBIR_ALPHA_CHANNEL_NONE`
+const ALPHA_CHANNEL_NONE: raw::c_int = -1;
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for f32 {}
+}
+
+/// Channel component types supported by stb (`u8`, `u16` and `f32`)
+pub trait Component: private::Sealed + Copy {
+    #[doc(hidden)]
+    const DATATYPE: sys::stbir_datatype;
+}
+
+impl Component for u8 {
+    const DATATYPE: sys::stbir_datatype = 0;
+}
+
+impl Component for u16 {
+    const DATATYPE: sys::stbir_datatype = 1;
+}
+
+impl Component for f32 {
+    const DATATYPE: sys::stbir_datatype = 3;
+}
+
+/// Easy-to-use API, resizes linear `u8` image with the default filter and clamped edges.
+/// Use 0 strides for tightly packed rows.
+#[allow(clippy::too_many_arguments)]
+pub fn stbir_resize_uint8(
+    input: &[u8],
+    input_w: i32,
+    input_h: i32,
+    input_stride_in_bytes: i32,
+    output: &mut [u8],
+    output_w: i32,
+    output_h: i32,
+    output_stride_in_bytes: i32,
+    num_channels: i32,
+) -> Option<()> {
+    stbir_resize(
+        input,
+        input_w,
+        input_h,
+        input_stride_in_bytes,
+        output,
+        output_w,
+        output_h,
+        output_stride_in_bytes,
+        num_channels,
+        &Options::default(),
+    )
+}
+
+/// Easy-to-use API, resizes linear `f32` image with the default filter and clamped edges.
+/// Use 0 strides for tightly packed rows.
+#[allow(clippy::too_many_arguments)]
+pub fn stbir_resize_float(
+    input: &[f32],
+    input_w: i32,
+    input_h: i32,
+    input_stride_in_bytes: i32,
+    output: &mut [f32],
+    output_w: i32,
+    output_h: i32,
+    output_stride_in_bytes: i32,
+    num_channels: i32,
+) -> Option<()> {
+    stbir_resize(
+        input,
+        input_w,
+        input_h,
+        input_stride_in_bytes,
+        output,
+        output_w,
+        output_h,
+        output_stride_in_bytes,
+        num_channels,
+        &Options::default(),
+    )
+}
+
+/// Easy-to-use API, resizes sRGB `u8` image with the default filter and clamped edges.
+/// The alpha channel (if any) is treated as linear, unless `ALPHA_USES_COLORSPACE` is set.
+/// Use 0 strides for tightly packed rows.
+#[allow(clippy::too_many_arguments)]
+pub fn stbir_resize_uint8_srgb(
+    input: &[u8],
+    input_w: i32,
+    input_h: i32,
+    input_stride_in_bytes: i32,
+    output: &mut [u8],
+    output_w: i32,
+    output_h: i32,
+    output_stride_in_bytes: i32,
+    num_channels: i32,
+    alpha_channel: Option<usize>,
+) -> Option<()> {
+    let options = Options {
+        alpha_channel,
+        colorspace: ColorSpace::Srgb,
+        ..Options::default()
+    };
+
+    stbir_resize(
+        input,
+        input_w,
+        input_h,
+        input_stride_in_bytes,
+        output,
+        output_w,
+        output_h,
+        output_stride_in_bytes,
+        num_channels,
+        &options,
+    )
+}
+
+/// Full-featured API, resizes `u8`, `u16` or `f32` image with explicit edge modes, filters,
+/// color space and alpha handling.
+/// Use 0 strides for tightly packed rows.
+#[allow(clippy::too_many_arguments)]
+pub fn stbir_resize<T: Component>(
+    input: &[T],
+    input_w: i32,
+    input_h: i32,
+    input_stride_in_bytes: i32,
+    output: &mut [T],
+    output_w: i32,
+    output_h: i32,
+    output_stride_in_bytes: i32,
+    num_channels: i32,
+    options: &Options,
+) -> Option<()> {
+    if num_channels <= 0 || num_channels as usize > MAX_CHANNELS {
+        return None;
+    }
+
+    let alpha_channel = match options.alpha_channel {
+        Some(alpha) if alpha >= num_channels as usize => return None,
+        Some(alpha) => alpha as raw::c_int,
+        None => ALPHA_CHANNEL_NONE,
+    };
+
+    let (input_row, input_stride) = stride::<T>(input_w, num_channels, input_stride_in_bytes)?;
+    check_size(input, input_h, input_stride, input_row)?;
+
+    let (output_row, output_stride) = stride::<T>(output_w, num_channels, output_stride_in_bytes)?;
+    check_size(output, output_h, output_stride, output_row)?;
+
+    let ret = unsafe {
+        sys::stbir_resize(
+            input.as_ptr().cast(),
+            input_w,
+            input_h,
+            input_stride_in_bytes,
+            output.as_mut_ptr().cast(),
+            output_w,
+            output_h,
+            output_stride_in_bytes,
+            T::DATATYPE,
+            num_channels,
+            alpha_channel,
+            options.flags(),
+            options.edge_horizontal as sys::stbir_edge,
+            options.edge_vertical as sys::stbir_edge,
+            options.filter_horizontal as sys::stbir_filter,
+            options.filter_vertical as sys::stbir_filter,
+            options.colorspace as sys::stbir_colorspace,
+            ptr::null_mut(),
+        )
+    };
+
+    if ret == 0 {
+        None
+    } else {
+        Some(())
+    }
+}
+
+/// Resizes image `input` to `output_w` x `output_h` pixels with the given options.
+/// Returns resized pixels with tightly packed rows.
+pub fn resize<T: Component + Default>(
+    input: ImageView<'_, T>,
+    output_w: i32,
+    output_h: i32,
+    options: &Options,
+) -> Option<Vec<T>> {
+    if output_w <= 0 || output_h <= 0 {
+        return None;
+    }
+
+    let input_stride_in_bytes = input.stride().checked_mul(size_of::<T>())?;
+    let size = (output_w as usize)
+        .checked_mul(output_h as usize)?
+        .checked_mul(input.channels() as usize)?;
+    let mut output = vec![T::default(); size];
+
+    stbir_resize(
+        input.as_slice(),
+        input.width(),
+        input.height(),
+        raw::c_int::try_from(input_stride_in_bytes).ok()?,
+        &mut output,
+        output_w,
+        output_h,
+        0,
+        input.channels(),
+        options,
+    )?;
+
+    Some(output)
+}
+
+/// Returns row size and stride in bytes, or `None` if the image width or stride are invalid.
+fn stride<T>(width: i32, num_channels: i32, stride_in_bytes: i32) -> Option<(usize, usize)> {
+    if width <= 0 || stride_in_bytes < 0 {
+        return None;
+    }
+
+    let row = (width as usize)
+        .checked_mul(num_channels as usize)?
+        .checked_mul(size_of::<T>())?;
+
+    match stride_in_bytes as usize {
+        0 => Some((row, row)),
+        stride if stride < row || stride % size_of::<T>() != 0 => None,
+        stride => Some((row, stride)),
+    }
+}
+
+/// Makes sure `buffer` can hold `height` rows of `stride` bytes, the last row being `row` bytes.
+fn check_size<T>(buffer: &[T], height: i32, stride: usize, row: usize) -> Option<()> {
+    if height <= 0 {
+        return None;
+    }
+
+    let size = stride.checked_mul(height as usize - 1)?.checked_add(row)?;
+    if std::mem::size_of_val(buffer) < size {
+        return None;
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_uint8() {
+        let input = [255_u8; 2 * 2 * 3];
+        let mut output = [0_u8; 4 * 4 * 3];
+
+        stbir_resize_uint8(&input, 2, 2, 0, &mut output, 4, 4, 0, 3).expect("Failed to resize");
+        assert!(output.iter().all(|c| *c == 255));
+    }
+
+    #[test]
+    fn resize_float() {
+        let input = [0.5_f32; 4 * 4];
+        let mut output = [0.0_f32; 2 * 2];
+
+        stbir_resize_float(&input, 4, 4, 0, &mut output, 2, 2, 0, 1).expect("Failed to resize");
+        for c in output.iter() {
+            assert!((c - 0.5).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn resize_uint16_options() {
+        let input = [u16::MAX; 4 * 4 * 2];
+        let mut output = [0_u16; 2 * 2 * 2];
+
+        let options = Options {
+            alpha_channel: Some(1),
+            colorspace: ColorSpace::Srgb,
+            ..Options::default()
+        }
+        .with_edge(Edge::Wrap)
+        .with_filter(Filter::Box);
+
+        stbir_resize(&input, 4, 4, 0, &mut output, 2, 2, 0, 2, &options).expect("Failed to resize");
+        assert!(output.iter().all(|c| *c == u16::MAX));
+    }
+
+    #[test]
+    fn resize_srgb_stride() {
+        // 2x2 RGBA with 4 bytes of padding per row
+        let input = [128_u8; 12 * 2];
+        let mut output = [0_u8; 4];
+
+        stbir_resize_uint8_srgb(&input, 2, 2, 12, &mut output, 1, 1, 0, 4, Some(3))
+            .expect("Failed to resize");
+        assert_eq!(output, [128; 4]);
+    }
+
+    #[test]
+    fn resize_invalid() {
+        let input = [0_u8; 2 * 2];
+        let mut output = [0_u8; 4 * 4];
+
+        // Input is too short
+        assert!(stbir_resize_uint8(&input, 2, 3, 0, &mut output, 4, 4, 0, 1).is_none());
+        // Output is too short
+        assert!(stbir_resize_uint8(&input, 2, 2, 0, &mut output, 4, 5, 0, 1).is_none());
+        // Stride is shorter than a row
+        assert!(stbir_resize_uint8(&input, 2, 2, 1, &mut output, 4, 4, 0, 1).is_none());
+        // Invalid channels
+        assert!(stbir_resize_uint8(&input, 1, 1, 0, &mut output, 4, 4, 0, 0).is_none());
+        assert!(
+            stbir_resize_uint8_srgb(&input, 2, 2, 0, &mut output, 4, 4, 0, 1, Some(1)).is_none()
+        );
+        // Invalid dimensions
+        assert!(stbir_resize_uint8(&input, 0, 2, 0, &mut output, 4, 4, 0, 1).is_none());
+    }
+
+    #[test]
+    fn resize_stride_last_row() {
+        // 2x2 grey rows 3 bytes apart, the last row is not padded
+        let input = [10_u8, 10, 99, 10, 10];
+        let mut output = [0_u8; 4];
+
+        stbir_resize_uint8(&input, 2, 2, 3, &mut output, 2, 2, 0, 1).expect("Failed to resize");
+        assert_eq!(output, [10; 4]);
+
+        // Last row is one byte short
+        assert!(stbir_resize_uint8(&input[..4], 2, 2, 3, &mut output, 2, 2, 0, 1).is_none());
+        // Output rows 3 bytes apart need 5 bytes
+        let mut output = [0_u8; 5];
+        stbir_resize_uint8(&input, 2, 2, 3, &mut output, 2, 2, 3, 1).expect("Failed to resize");
+        assert_eq!(output, [10, 10, 0, 10, 10]);
+        assert!(stbir_resize_uint8(&input, 2, 2, 3, &mut output[..4], 2, 2, 3, 1).is_none());
+    }
+
+    #[test]
+    fn resize_view() {
+        // 2x2 grey view into a 3x2 image, last row is not padded
+        let input = [10_u8, 10, 99, 10, 10];
+        let view = ImageView::with_stride(2, 2, 1, 3, &input).unwrap();
+
+        let output = resize(view, 3, 3, &Options::default()).expect("Failed to resize");
+        assert_eq!(output, [10; 9]);
+
+        assert!(resize(view, 0, 3, &Options::default()).is_none());
+    }
+}
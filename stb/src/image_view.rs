@@ -0,0 +1,189 @@
+//! Borrowed image pixels with dimensions and layout, accepted by image writing, resizing and DXT
+//! compression, and returned by decoded images.
+//!
+//! Rust implementation notes:
+//!
+//! - Rows are `stride` elements apart, the last row doesn't need to be padded, so a view can
+//!   point to a part of a larger image.
+//! - Dimensions, channels and stride are checked against the slice length once when the view is
+//!   created, functions taking a view don't need to validate buffer sizes again.
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// Borrowed image pixels, rows are `stride` elements apart (`stride` >= `width * channels`)
+pub struct ImageView<'a, T> {
+    width: i32,
+    height: i32,
+    channels: i32,
+    stride: usize,
+    data: &'a [T],
+}
+
+impl<'a, T> ImageView<'a, T> {
+    /// Creates view of pixels with tightly packed rows.
+    /// Returns `None` if dimensions or channels are not positive or `data` is too short.
+    pub fn new(width: i32, height: i32, channels: i32, data: &'a [T]) -> Option<Self> {
+        let stride = (width.max(0) as usize).saturating_mul(channels.max(0) as usize);
+        Self::with_stride(width, height, channels, stride, data)
+    }
+
+    /// Creates view of pixels with rows `stride` elements apart.
+    /// Returns `None` if dimensions or channels are not positive, `stride` is shorter than a row
+    /// or `data` can't hold `height` rows.
+    pub fn with_stride(
+        width: i32,
+        height: i32,
+        channels: i32,
+        stride: usize,
+        data: &'a [T],
+    ) -> Option<Self> {
+        if width <= 0 || height <= 0 || channels <= 0 {
+            return None;
+        }
+
+        let row = (width as usize).checked_mul(channels as usize)?;
+        if stride < row {
+            return None;
+        }
+
+        let size = stride.checked_mul(height as usize - 1)?.checked_add(row)?;
+        if data.len() < size {
+            return None;
+        }
+
+        Some(ImageView {
+            width,
+            height,
+            channels,
+            stride,
+            data,
+        })
+    }
+
+    /// Image width in pixels
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Number of channels per pixel
+    pub fn channels(&self) -> i32 {
+        self.channels
+    }
+
+    /// Distance between rows in elements
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Returns `true` if there is no padding between rows
+    pub fn is_packed(&self) -> bool {
+        self.stride == self.row_size()
+    }
+
+    /// Returns viewed memory as a slice (including row padding, if any)
+    pub fn as_slice(&self) -> &'a [T] {
+        self.data
+    }
+
+    /// Returns pixels of the row `y` (without padding)
+    pub fn row(&self, y: i32) -> &'a [T] {
+        assert!(y >= 0 && y < self.height, "y is out of bounds");
+
+        let start = y as usize * self.stride;
+        &self.data[start..start + self.row_size()]
+    }
+
+    /// Returns an iterator over image rows (without padding)
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> + '_ {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    /// Returns channels of the pixel at (x, y)
+    pub fn pixel(&self, x: i32, y: i32) -> &'a [T] {
+        assert!(x >= 0 && x < self.width, "x is out of bounds");
+
+        let channels = self.channels as usize;
+        let offset = x as usize * channels;
+        &self.row(y)[offset..offset + channels]
+    }
+
+    fn row_size(&self) -> usize {
+        self.width as usize * self.channels as usize
+    }
+}
+
+impl<'a, T: Clone> ImageView<'a, T> {
+    /// Returns pixels with tightly packed rows, copies them only if rows are padded
+    pub fn to_packed(&self) -> Cow<'a, [T]> {
+        let size = self.row_size() * self.height as usize;
+        if self.is_packed() {
+            return Cow::Borrowed(&self.data[..size]);
+        }
+
+        let mut data = Vec::with_capacity(size);
+        for row in self.rows() {
+            data.extend_from_slice(row);
+        }
+        Cow::Owned(data)
+    }
+}
+
+// Derives would require `T: Clone`, while only the reference is copied
+impl<T> Clone for ImageView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ImageView<'_, T> {}
+
+impl<T> fmt::Debug for ImageView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageView")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("channels", &self.channels)
+            .field("stride", &self.stride)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_with_stride() {
+        // 2x2 grey image with 3 elements stride, last row without padding
+        let data = [1_u8, 2, 0, 3, 4];
+        let view = ImageView::with_stride(2, 2, 1, 3, &data).unwrap();
+
+        assert!(!view.is_packed());
+        assert_eq!(view.row(1), &[3, 4]);
+        assert_eq!(view.pixel(1, 0), &[2]);
+        assert_eq!(view.rows().count(), 2);
+        assert_eq!(view.to_packed().as_ref(), &[1, 2, 3, 4]);
+
+        assert!(ImageView::with_stride(2, 2, 1, 3, &data[..4]).is_none());
+        assert!(ImageView::with_stride(2, 2, 1, 1, &data).is_none());
+        assert!(ImageView::new(0, 2, 1, &data).is_none());
+    }
+
+    #[test]
+    fn packed_view_borrows() {
+        let data = [7_u16; 2 * 3 * 2 + 1];
+        let view = ImageView::new(2, 3, 2, &data).unwrap();
+
+        assert!(view.is_packed());
+        match view.to_packed() {
+            Cow::Borrowed(slice) => assert_eq!(slice.len(), 12),
+            Cow::Owned(_) => panic!("Packed view must not be copied"),
+        }
+    }
+}
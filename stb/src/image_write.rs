@@ -1,17 +1,90 @@
-//! A module for writing images to C stdio or a callback.
+//! A module for writing images to C stdio, a callback or a file path.
 //!
 //! The PNG output is not optimal; it is 20-50% larger than the file
 //! written by a decent optimizing implementation; though providing a custom
 //! zlib compress function (see STBIW_ZLIB_COMPRESS) can mitigate that.
 //! This library is designed for source code compactness and simplicity,
 //! not optimal image file size or run-time performance.
+//!
+//! Rust implementation notes:
+//!
+//! - `stbi_write_*` functions mirror the C API with positional arguments, the rest take an
+//!   `ImageView`, so decoded images can be written with `write_png(path, image.view())`.
+//! - `write_png`, `write_bmp`, `write_tga`, `write_jpg` and `write_hdr` take any path, encode
+//!   the image in memory and write the file with Rust IO, so non-UTF-8 paths work and nothing is
+//!   written if encoding fails. They return `WriteError` describing the failure.
+//! - `write_*_to_writer` functions encode into any `io::Write`, `encode_*_to_vec` return encoded
+//!   image bytes.
+//! - `stbi_write_*_to_func` callbacks receive encoded file bytes, including HDR encoder, in a
+//!   single call once the image is encoded.
+//! - stb encoder settings are global, `EncoderOptions` applies them per call while holding
+//!   a lock, so concurrent writers with different settings don't interfere. stb encodes into
+//!   memory while the lock is held, writers and `_to_func` callbacks get the encoded image
+//!   after the lock is released, so they are free to encode images themselves.
+//! - All functions check dimensions, number of components and stride against the buffer
+//!   length before calling stb, returning `WriteError` instead of reading out of bounds.
 
 use stb_sys as sys;
-use std::os::raw::c_char;
+use std::any::Any;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::error;
 use std::ffi::c_void;
 use std::ffi::CStr;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
 use std::os::raw;
+use std::os::raw::c_char;
+use std::panic;
+use std::path::Path;
 use std::slice;
+use std::sync::{Mutex, PoisonError};
+
+use crate::image_view::ImageView;
+
+/// Image writing error
+#[derive(Debug)]
+pub enum WriteError {
+    /// Image dimensions, components or other arguments are invalid, holds the description
+    InvalidArgument(String),
+    /// Buffer holds less than `required` elements needed for image dimensions and stride
+    BufferTooSmall { required: usize, actual: usize },
+    /// stb failed to encode the image
+    Encode,
+    /// Failed to write encoded image
+    Io(io::Error),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+            WriteError::BufferTooSmall { required, actual } => write!(
+                f,
+                "buffer too small: {} elements required, {} provided",
+                required, actual
+            ),
+            WriteError::Encode => f.write_str("failed to encode image"),
+            WriteError::Io(err) => write!(f, "failed to write image: {}", err),
+        }
+    }
+}
+
+impl error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WriteError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> Self {
+        WriteError::Io(err)
+    }
+}
 
 pub fn stbi_write_png(
     filename: &CStr,
@@ -20,8 +93,10 @@ pub fn stbi_write_png(
     comp: i32,
     buffer: &[u8],
     stride_in_bytes: i32,
-) -> Option<()> {
-    let ret = unsafe {
+) -> Result<(), WriteError> {
+    validate(w, h, comp, stride_in_bytes, buffer.len())?;
+
+    let ret = EncoderOptions::default().with_settings(|| unsafe {
         sys::stbi_write_png(
             filename.as_ptr() as *mut c_char,
             w,
@@ -30,17 +105,20 @@ pub fn stbi_write_png(
             buffer.as_ptr() as *const c_void,
             stride_in_bytes,
         )
-    };
-    // Each function returns 0 on failure and non-0 on success.
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    });
+    encode_result(ret)
 }
 
-pub fn stbi_write_bmp(filename: &CStr, w: i32, h: i32, comp: i32, buffer: &[u8]) -> Option<()> {
-    let ret = unsafe {
+pub fn stbi_write_bmp(
+    filename: &CStr,
+    w: i32,
+    h: i32,
+    comp: i32,
+    buffer: &[u8],
+) -> Result<(), WriteError> {
+    validate(w, h, comp, 0, buffer.len())?;
+
+    let ret = EncoderOptions::default().with_settings(|| unsafe {
         sys::stbi_write_bmp(
             filename.as_ptr(),
             w,
@@ -48,17 +126,20 @@ pub fn stbi_write_bmp(filename: &CStr, w: i32, h: i32, comp: i32, buffer: &[u8])
             comp,
             buffer.as_ptr() as *const c_void,
         )
-    };
-    // Each function returns 0 on failure and non-0 on success.
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    });
+    encode_result(ret)
 }
 
-pub fn stbi_write_tga(filename: &CStr, w: i32, h: i32, comp: i32, buffer: &[u8]) -> Option<()> {
-    let ret = unsafe {
+pub fn stbi_write_tga(
+    filename: &CStr,
+    w: i32,
+    h: i32,
+    comp: i32,
+    buffer: &[u8],
+) -> Result<(), WriteError> {
+    validate(w, h, comp, 0, buffer.len())?;
+
+    let ret = EncoderOptions::default().with_settings(|| unsafe {
         sys::stbi_write_tga(
             filename.as_ptr(),
             w,
@@ -66,24 +147,29 @@ pub fn stbi_write_tga(filename: &CStr, w: i32, h: i32, comp: i32, buffer: &[u8])
             comp,
             buffer.as_ptr() as *const c_void,
         )
-    };
-    // Each function returns 0 on failure and non-0 on success.
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    });
+    encode_result(ret)
 }
 
-pub fn stbi_write_hdr(filename: &CStr, w: i32, h: i32, comp: i32, buffer: &[f32]) -> Option<()> {
-    let ret =
-        unsafe { sys::stbi_write_hdr(filename.as_ptr() as *mut c_char, w, h, comp, buffer.as_ptr()) };
-    // Each function returns 0 on failure and non-0 on success.
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+pub fn stbi_write_hdr(
+    filename: &CStr,
+    w: i32,
+    h: i32,
+    comp: i32,
+    buffer: &[f32],
+) -> Result<(), WriteError> {
+    validate(w, h, comp, 0, buffer.len())?;
+
+    let ret = EncoderOptions::default().with_settings(|| unsafe {
+        sys::stbi_write_hdr(
+            filename.as_ptr() as *mut c_char,
+            w,
+            h,
+            comp,
+            buffer.as_ptr(),
+        )
+    });
+    encode_result(ret)
 }
 
 pub fn stbi_write_jpg(
@@ -93,8 +179,10 @@ pub fn stbi_write_jpg(
     comp: i32,
     buffer: &[u8],
     quality: i32,
-) -> Option<()> {
-    let ret = unsafe {
+) -> Result<(), WriteError> {
+    validate(w, h, comp, 0, buffer.len())?;
+
+    let ret = EncoderOptions::default().with_settings(|| unsafe {
         sys::stbi_write_jpg(
             filename.as_ptr(),
             w,
@@ -103,26 +191,11 @@ pub fn stbi_write_jpg(
             buffer.as_ptr() as *const c_void,
             quality,
         )
-    };
-    // Each function returns 0 on failure and non-0 on success.
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
-}
-
-extern "C" fn write_func<F, T>(context: *mut raw::c_void, data: *mut raw::c_void, size: raw::c_int)
-where
-    F: FnMut(&[T]),
-{
-    let buffer = unsafe { slice::from_raw_parts_mut(data as *mut T, size as _) };
-    // See https://s3.amazonaws.com/temp.michaelfbryan.com/callbacks/index.html
-    let f: &mut F = unsafe { &mut *(context as *mut F) };
-
-    f(buffer)
+    });
+    encode_result(ret)
 }
 
+/// Encodes PNG image in memory and passes the whole file to `func` in a single call
 pub fn stbi_write_png_to_func<F>(
     func: &mut F,
     w: i32,
@@ -130,113 +203,92 @@ pub fn stbi_write_png_to_func<F>(
     comp: i32,
     buffer: &[u8],
     stride_in_bytes: i32,
-) -> Option<()>
+) -> Result<(), WriteError>
 where
     F: FnMut(&[u8]),
 {
-    let ret = unsafe {
+    validate(w, h, comp, stride_in_bytes, buffer.len())?;
+
+    let data = EncoderOptions::default().encode(|write, context| unsafe {
         sys::stbi_write_png_to_func(
-            Some(write_func::<F, u8>),
-            func as *mut F as *mut c_void,
+            write,
+            context,
             w,
             h,
             comp,
             buffer.as_ptr() as *const c_void,
             stride_in_bytes,
         )
-    };
+    })?;
 
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    func(&data);
+    Ok(())
 }
 
+/// Encodes BMP image in memory and passes the whole file to `func` in a single call
 pub fn stbi_write_bmp_to_func<F>(
     func: &mut F,
     w: i32,
     h: i32,
     comp: i32,
     buffer: &[u8],
-) -> Option<()>
+) -> Result<(), WriteError>
 where
     F: FnMut(&[u8]),
 {
-    let ret = unsafe {
-        sys::stbi_write_bmp_to_func(
-            Some(write_func::<F, u8>),
-            func as *mut F as *mut c_void,
-            w,
-            h,
-            comp,
-            buffer.as_ptr() as *const c_void,
-        )
-    };
+    validate(w, h, comp, 0, buffer.len())?;
 
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    let data = EncoderOptions::default().encode(|write, context| unsafe {
+        sys::stbi_write_bmp_to_func(write, context, w, h, comp, buffer.as_ptr() as *const c_void)
+    })?;
+
+    func(&data);
+    Ok(())
 }
 
+/// Encodes TGA image in memory and passes the whole file to `func` in a single call
 pub fn stbi_write_tga_to_func<F>(
     func: &mut F,
     w: i32,
     h: i32,
     comp: i32,
     buffer: &[u8],
-) -> Option<()>
+) -> Result<(), WriteError>
 where
     F: FnMut(&[u8]),
 {
-    let ret = unsafe {
-        sys::stbi_write_tga_to_func(
-            Some(write_func::<F, u8>),
-            func as *mut F as *mut c_void,
-            w,
-            h,
-            comp,
-            buffer.as_ptr() as *const c_void,
-        )
-    };
+    validate(w, h, comp, 0, buffer.len())?;
 
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    let data = EncoderOptions::default().encode(|write, context| unsafe {
+        sys::stbi_write_tga_to_func(write, context, w, h, comp, buffer.as_ptr() as *const c_void)
+    })?;
+
+    func(&data);
+    Ok(())
 }
 
+/// Encodes Radiance HDR image in memory and passes the whole file to `func` in a single call
 pub fn stbi_write_hdr_to_func<F>(
     func: &mut F,
     w: i32,
     h: i32,
     comp: i32,
     buffer: &[f32],
-) -> Option<()>
+) -> Result<(), WriteError>
 where
-    F: FnMut(&[f32]),
+    F: FnMut(&[u8]),
 {
-    let ret = unsafe {
-        sys::stbi_write_hdr_to_func(
-            Some(write_func::<F, f32>),
-            func as *mut F as *mut c_void,
-            w,
-            h,
-            comp,
-            buffer.as_ptr() as *const f32,
-        )
-    };
+    validate(w, h, comp, 0, buffer.len())?;
 
-    if ret == 0 {
-        None
-    } else {
-        Some(())
-    }
+    let data = EncoderOptions::default().encode(|write, context| unsafe {
+        sys::stbi_write_hdr_to_func(write, context, w, h, comp, buffer.as_ptr() as *const f32)
+    })?;
+
+    func(&data);
+    Ok(())
 }
 
+/// Encodes JPEG image in memory and passes the whole file to `func` in a single call
 pub fn stbi_write_jpg_to_func<F>(
     func: &mut F,
     w: i32,
@@ -244,29 +296,508 @@ pub fn stbi_write_jpg_to_func<F>(
     comp: i32,
     buffer: &[u8],
     quality: i32,
-) -> Option<()>
+) -> Result<(), WriteError>
 where
     F: FnMut(&[u8]),
 {
-    let ret = unsafe {
+    validate(w, h, comp, 0, buffer.len())?;
+
+    let data = EncoderOptions::default().encode(|write, context| unsafe {
         sys::stbi_write_jpg_to_func(
-            Some(write_func::<F, u8>),
-            func as *mut F as *mut c_void,
+            write,
+            context,
             w,
             h,
             comp,
             buffer.as_ptr() as *const c_void,
             quality,
         )
-    };
+    })?;
+
+    func(&data);
+    Ok(())
+}
 
+/// Each function returns 0 on failure and non-0 on success.
+fn encode_result(ret: raw::c_int) -> Result<(), WriteError> {
     if ret == 0 {
-        None
+        Err(WriteError::Encode)
     } else {
-        Some(())
+        Ok(())
+    }
+}
+
+/// Checks image dimensions, number of components (1 - grey, 2 - grey/alpha, 3 - RGB,
+/// 4 - RGBA) and that `len` buffer elements hold `h` rows `stride` elements apart
+/// (0 for packed rows), so stb never reads past the end of the buffer.
+fn validate(w: i32, h: i32, comp: i32, stride: i32, len: usize) -> Result<(), WriteError> {
+    if w <= 0 || h <= 0 {
+        return Err(WriteError::InvalidArgument(format!(
+            "invalid image dimensions {}x{}",
+            w, h
+        )));
+    }
+
+    if !(1..=4).contains(&comp) {
+        return Err(WriteError::InvalidArgument(format!(
+            "invalid number of components {}",
+            comp
+        )));
+    }
+
+    // stb computes buffer offsets and allocation sizes with `int`
+    let row = w as i64 * comp as i64;
+    let stride = if stride == 0 { row } else { stride as i64 };
+    if stride < row {
+        return Err(WriteError::InvalidArgument(format!(
+            "stride {} is less than row size {}",
+            stride, row
+        )));
+    }
+    if stride * h as i64 > raw::c_int::MAX as i64 {
+        return Err(WriteError::InvalidArgument(format!(
+            "image {}x{} is too large",
+            w, h
+        )));
+    }
+
+    // Padding after the last row is not required
+    let required = (stride * (h as i64 - 1) + row) as usize;
+    if len < required {
+        return Err(WriteError::BufferTooSmall {
+            required,
+            actual: len,
+        });
+    }
+
+    Ok(())
+}
+
+/// Collects encoder output in memory while the settings lock is held.
+/// A panic while collecting is caught and resumed once stb returns, as it can't unwind
+/// through C frames.
+struct Encoded {
+    data: Vec<u8>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl Encoded {
+    /// Appends `size` bytes of encoded image
+    extern "C" fn write(context: *mut raw::c_void, data: *mut raw::c_void, size: raw::c_int) {
+        let encoded = unsafe { &mut *(context as *mut Encoded) };
+        if encoded.panic.is_some() {
+            return;
+        }
+
+        let data = unsafe { slice::from_raw_parts(data as *const u8, size as usize) };
+        let ret = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            encoded.data.extend_from_slice(data);
+        }));
+
+        if let Err(panic) = ret {
+            encoded.panic = Some(panic);
+        }
+    }
+}
+
+/// Checks `image` channels and size against stb limits, returns stride in elements
+fn validate_view<T>(image: &ImageView<'_, T>) -> Result<i32, WriteError> {
+    let stride = i32::try_from(image.stride()).map_err(|_| {
+        WriteError::InvalidArgument(format!("stride {} is too large", image.stride()))
+    })?;
+
+    validate(
+        image.width(),
+        image.height(),
+        image.channels(),
+        stride,
+        image.as_slice().len(),
+    )?;
+
+    Ok(stride)
+}
+
+/// Returns `image` pixels with tightly packed rows for encoders not supporting stride
+fn packed<'a, T: Clone>(image: &ImageView<'a, T>) -> Result<Cow<'a, [T]>, WriteError> {
+    validate_view(image)?;
+    Ok(image.to_packed())
+}
+
+/// PNG filter applied to every row
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PngFilter {
+    None = 0,
+    Sub = 1,
+    Up = 2,
+    Average = 3,
+    Paeth = 4,
+}
+
+/// stb_image_write keeps encoder settings in globals, so encoders hold this lock while
+/// the settings are applied
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Per-call encoder settings.
+///
+/// stb_image_write has no per-thread settings, so `stbi_write_png_compression_level`,
+/// `stbi_write_force_png_filter`, `stbi_write_tga_with_rle` and
+/// `stbi_flip_vertically_on_write` globals are set right before encoding, and all encoders
+/// in this module are serialized with a lock, so concurrent writes don't interfere.
+/// Functions not taking options encode with default settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EncoderOptions {
+    png_compression_level: i32,
+    png_filter: Option<PngFilter>,
+    tga_rle: bool,
+    flip_vertically: bool,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        EncoderOptions {
+            png_compression_level: 8,
+            png_filter: None,
+            tga_rle: true,
+            flip_vertically: false,
+        }
+    }
+}
+
+impl EncoderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// zlib compression level used for PNG, higher values compress better but slower
+    /// (8 by default)
+    pub fn png_compression_level(mut self, level: i32) -> Self {
+        self.png_compression_level = level;
+        self
+    }
+
+    /// Filter used for all PNG rows, `None` picks the best filter for each row (default)
+    pub fn png_filter(mut self, filter: Option<PngFilter>) -> Self {
+        self.png_filter = filter;
+        self
+    }
+
+    /// Compress TGA images with RLE (enabled by default)
+    pub fn tga_rle(mut self, rle: bool) -> Self {
+        self.tga_rle = rle;
+        self
+    }
+
+    /// Flip the image vertically, so the last row is written first
+    pub fn flip_vertically(mut self, flip: bool) -> Self {
+        self.flip_vertically = flip;
+        self
+    }
+
+    /// Applies settings to stb globals and runs `f` while holding the settings lock
+    fn with_settings<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = SETTINGS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+        unsafe {
+            sys::stbi_write_png_compression_level = self.png_compression_level;
+            sys::stbi_write_force_png_filter = self.png_filter.map_or(-1, |filter| filter as i32);
+            sys::stbi_write_tga_with_rle = self.tga_rle as raw::c_int;
+            sys::stbi_flip_vertically_on_write(self.flip_vertically as raw::c_int);
+        }
+
+        f()
+    }
+
+    /// Encodes image in memory with these settings.
+    /// `encode` gets the callback and context to pass to stb `_to_func` function.
+    fn encode<F>(&self, encode: F) -> Result<Vec<u8>, WriteError>
+    where
+        F: FnOnce(sys::stbi_write_func, *mut c_void) -> raw::c_int,
+    {
+        let mut encoded = Encoded {
+            data: Vec::new(),
+            panic: None,
+        };
+        let context = &mut encoded as *mut Encoded as *mut c_void;
+        let ret = self.with_settings(|| encode(Some(Encoded::write), context));
+
+        if let Some(panic) = encoded.panic {
+            panic::resume_unwind(panic);
+        }
+
+        encode_result(ret)?;
+        Ok(encoded.data)
+    }
+
+    /// Writes PNG image into `writer`
+    /// The whole file is encoded in memory first, then written with a single `write_all`.
+    pub fn write_png_to_writer<W>(
+        &self,
+        writer: &mut W,
+        image: ImageView<'_, u8>,
+    ) -> Result<(), WriteError>
+    where
+        W: io::Write,
+    {
+        writer.write_all(&self.encode_png(image)?)?;
+        Ok(())
+    }
+
+    /// Encodes PNG image in memory
+    fn encode_png(&self, image: ImageView<'_, u8>) -> Result<Vec<u8>, WriteError> {
+        let stride = validate_view(&image)?;
+        self.encode(|func, context| unsafe {
+            sys::stbi_write_png_to_func(
+                func,
+                context,
+                image.width(),
+                image.height(),
+                image.channels(),
+                image.as_slice().as_ptr() as *const c_void,
+                stride,
+            )
+        })
+    }
+
+    /// Writes BMP image into `writer`, alpha channel is ignored
+    /// The whole file is encoded in memory first, then written with a single `write_all`.
+    pub fn write_bmp_to_writer<W>(
+        &self,
+        writer: &mut W,
+        image: ImageView<'_, u8>,
+    ) -> Result<(), WriteError>
+    where
+        W: io::Write,
+    {
+        writer.write_all(&self.encode_bmp(image)?)?;
+        Ok(())
+    }
+
+    /// Encodes BMP image in memory, alpha channel is ignored
+    fn encode_bmp(&self, image: ImageView<'_, u8>) -> Result<Vec<u8>, WriteError> {
+        let data = packed(&image)?;
+        self.encode(|func, context| unsafe {
+            sys::stbi_write_bmp_to_func(
+                func,
+                context,
+                image.width(),
+                image.height(),
+                image.channels(),
+                data.as_ptr() as *const c_void,
+            )
+        })
+    }
+
+    /// Writes TGA image into `writer`
+    /// The whole file is encoded in memory first, then written with a single `write_all`.
+    pub fn write_tga_to_writer<W>(
+        &self,
+        writer: &mut W,
+        image: ImageView<'_, u8>,
+    ) -> Result<(), WriteError>
+    where
+        W: io::Write,
+    {
+        writer.write_all(&self.encode_tga(image)?)?;
+        Ok(())
+    }
+
+    /// Encodes TGA image in memory
+    fn encode_tga(&self, image: ImageView<'_, u8>) -> Result<Vec<u8>, WriteError> {
+        let data = packed(&image)?;
+        self.encode(|func, context| unsafe {
+            sys::stbi_write_tga_to_func(
+                func,
+                context,
+                image.width(),
+                image.height(),
+                image.channels(),
+                data.as_ptr() as *const c_void,
+            )
+        })
+    }
+
+    /// Writes JPEG image into `writer`, `quality` is between 1 and 100, alpha channel is ignored
+    /// The whole file is encoded in memory first, then written with a single `write_all`.
+    pub fn write_jpg_to_writer<W>(
+        &self,
+        writer: &mut W,
+        image: ImageView<'_, u8>,
+        quality: i32,
+    ) -> Result<(), WriteError>
+    where
+        W: io::Write,
+    {
+        writer.write_all(&self.encode_jpg(image, quality)?)?;
+        Ok(())
+    }
+
+    /// Encodes JPEG image in memory, `quality` is between 1 and 100, alpha channel is ignored
+    fn encode_jpg(&self, image: ImageView<'_, u8>, quality: i32) -> Result<Vec<u8>, WriteError> {
+        let data = packed(&image)?;
+        self.encode(|func, context| unsafe {
+            sys::stbi_write_jpg_to_func(
+                func,
+                context,
+                image.width(),
+                image.height(),
+                image.channels(),
+                data.as_ptr() as *const c_void,
+                quality,
+            )
+        })
+    }
+
+    /// Writes Radiance HDR image into `writer` from linear floating point data
+    /// The whole file is encoded in memory first, then written with a single `write_all`.
+    pub fn write_hdr_to_writer<W>(
+        &self,
+        writer: &mut W,
+        image: ImageView<'_, f32>,
+    ) -> Result<(), WriteError>
+    where
+        W: io::Write,
+    {
+        writer.write_all(&self.encode_hdr(image)?)?;
+        Ok(())
+    }
+
+    /// Encodes Radiance HDR image in memory from linear floating point data
+    fn encode_hdr(&self, image: ImageView<'_, f32>) -> Result<Vec<u8>, WriteError> {
+        let data = packed(&image)?;
+        self.encode(|func, context| unsafe {
+            sys::stbi_write_hdr_to_func(
+                func,
+                context,
+                image.width(),
+                image.height(),
+                image.channels(),
+                data.as_ptr(),
+            )
+        })
     }
 }
 
+/// Writes PNG image into `writer`
+/// The whole file is encoded in memory first, then written with a single `write_all`.
+pub fn write_png_to_writer<W>(writer: &mut W, image: ImageView<'_, u8>) -> Result<(), WriteError>
+where
+    W: io::Write,
+{
+    EncoderOptions::default().write_png_to_writer(writer, image)
+}
+
+/// Writes BMP image into `writer`, alpha channel is ignored
+/// The whole file is encoded in memory first, then written with a single `write_all`.
+pub fn write_bmp_to_writer<W>(writer: &mut W, image: ImageView<'_, u8>) -> Result<(), WriteError>
+where
+    W: io::Write,
+{
+    EncoderOptions::default().write_bmp_to_writer(writer, image)
+}
+
+/// Writes TGA image into `writer`
+/// The whole file is encoded in memory first, then written with a single `write_all`.
+pub fn write_tga_to_writer<W>(writer: &mut W, image: ImageView<'_, u8>) -> Result<(), WriteError>
+where
+    W: io::Write,
+{
+    EncoderOptions::default().write_tga_to_writer(writer, image)
+}
+
+/// Writes JPEG image into `writer`, `quality` is between 1 and 100, alpha channel is ignored
+/// The whole file is encoded in memory first, then written with a single `write_all`.
+pub fn write_jpg_to_writer<W>(
+    writer: &mut W,
+    image: ImageView<'_, u8>,
+    quality: i32,
+) -> Result<(), WriteError>
+where
+    W: io::Write,
+{
+    EncoderOptions::default().write_jpg_to_writer(writer, image, quality)
+}
+
+/// Writes Radiance HDR image into `writer` from linear floating point data
+/// The whole file is encoded in memory first, then written with a single `write_all`.
+pub fn write_hdr_to_writer<W>(writer: &mut W, image: ImageView<'_, f32>) -> Result<(), WriteError>
+where
+    W: io::Write,
+{
+    EncoderOptions::default().write_hdr_to_writer(writer, image)
+}
+
+/// Encodes image in memory with `encode`, then creates file at `path` and writes it.
+/// No file is created if the image is invalid or fails to encode,
+/// and the file is removed if writing it fails.
+fn write_file<F>(path: &Path, encode: F) -> Result<(), WriteError>
+where
+    F: FnOnce() -> Result<Vec<u8>, WriteError>,
+{
+    let data = encode()?;
+
+    let mut file = fs::File::create(path)?;
+    if let Err(err) = file.write_all(&data) {
+        drop(file);
+        let _ = fs::remove_file(path);
+        return Err(WriteError::Io(err));
+    }
+    Ok(())
+}
+
+/// Writes PNG file
+pub fn write_png(path: impl AsRef<Path>, image: ImageView<'_, u8>) -> Result<(), WriteError> {
+    write_file(path.as_ref(), || encode_png_to_vec(image))
+}
+
+/// Writes BMP file, alpha channel is ignored
+pub fn write_bmp(path: impl AsRef<Path>, image: ImageView<'_, u8>) -> Result<(), WriteError> {
+    write_file(path.as_ref(), || encode_bmp_to_vec(image))
+}
+
+/// Writes TGA file
+pub fn write_tga(path: impl AsRef<Path>, image: ImageView<'_, u8>) -> Result<(), WriteError> {
+    write_file(path.as_ref(), || encode_tga_to_vec(image))
+}
+
+/// Writes JPEG file, `quality` is between 1 and 100, alpha channel is ignored
+pub fn write_jpg(
+    path: impl AsRef<Path>,
+    image: ImageView<'_, u8>,
+    quality: i32,
+) -> Result<(), WriteError> {
+    write_file(path.as_ref(), || encode_jpg_to_vec(image, quality))
+}
+
+/// Writes Radiance HDR file from linear floating point data
+pub fn write_hdr(path: impl AsRef<Path>, image: ImageView<'_, f32>) -> Result<(), WriteError> {
+    write_file(path.as_ref(), || encode_hdr_to_vec(image))
+}
+
+/// Encodes PNG image in memory
+pub fn encode_png_to_vec(image: ImageView<'_, u8>) -> Result<Vec<u8>, WriteError> {
+    EncoderOptions::default().encode_png(image)
+}
+
+/// Encodes BMP image in memory, alpha channel is ignored
+pub fn encode_bmp_to_vec(image: ImageView<'_, u8>) -> Result<Vec<u8>, WriteError> {
+    EncoderOptions::default().encode_bmp(image)
+}
+
+/// Encodes TGA image in memory
+pub fn encode_tga_to_vec(image: ImageView<'_, u8>) -> Result<Vec<u8>, WriteError> {
+    EncoderOptions::default().encode_tga(image)
+}
+
+/// Encodes JPEG image in memory, `quality` is between 1 and 100, alpha channel is ignored
+pub fn encode_jpg_to_vec(image: ImageView<'_, u8>, quality: i32) -> Result<Vec<u8>, WriteError> {
+    EncoderOptions::default().encode_jpg(image, quality)
+}
+
+/// Encodes Radiance HDR image in memory from linear floating point data
+pub fn encode_hdr_to_vec(image: ImageView<'_, f32>) -> Result<Vec<u8>, WriteError> {
+    EncoderOptions::default().encode_hdr(image)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,4 +833,317 @@ mod tests {
         .expect("Failed to write BMP to func");
         assert_ne!(counter, 0);
     }
+
+    #[test]
+    fn write_png_path() {
+        let mut path = std::env::temp_dir();
+        path.push("stb_write_png_path.png");
+
+        let image = ImageView::new(2, 1, 3, &[255, 0, 0, 0, 0, 255]).unwrap();
+        write_png(&path, image).expect("Failed to write PNG");
+
+        let data = fs::read(&path).expect("Failed to read PNG file");
+        fs::remove_file(&path).expect("Failed to remove PNG file");
+        assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn write_non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut path = std::env::temp_dir();
+        path.push(OsStr::from_bytes(b"stb_write_\xff.tga"));
+
+        write_tga(&path, ImageView::new(1, 1, 1, &[1]).unwrap()).expect("Failed to write TGA");
+
+        fs::metadata(&path).expect("Failed to check whether TGA file exists");
+        fs::remove_file(&path).expect("Failed to remove TGA file");
+    }
+
+    #[test]
+    fn write_path_errors() {
+        let mut path = std::env::temp_dir();
+        path.push("stb_write_path_errors.bmp");
+
+        // stb encoders support up to 4 channels
+        let image = ImageView::new(1, 1, 5, &[1; 5]).unwrap();
+
+        let err = super::write_bmp(&path, image).unwrap_err();
+        assert!(matches!(err, WriteError::InvalidArgument(_)));
+        assert!(fs::metadata(&path).is_err());
+
+        // Existing file isn't truncated if the image fails to encode
+        fs::write(&path, b"existing").expect("Failed to write file");
+        let err = write_jpg(&path, image, 90).unwrap_err();
+        assert!(matches!(err, WriteError::InvalidArgument(_)));
+        let data = fs::read(&path).expect("Failed to read file");
+        fs::remove_file(&path).expect("Failed to remove file");
+        assert_eq!(data, b"existing");
+
+        path.push("missing_dir/test.bmp");
+        let image = ImageView::new(1, 1, 1, &[1]).unwrap();
+        let err = super::write_bmp(&path, image).unwrap_err();
+        assert!(matches!(err, WriteError::Io(_)));
+    }
+
+    #[test]
+    fn write_to_failing_writer() {
+        struct Failing;
+
+        impl io::Write for Failing {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let image = ImageView::new(1, 1, 3, &[1.0; 3]).unwrap();
+        let err = write_hdr_to_writer(&mut Failing, image).unwrap_err();
+        assert_eq!(err.to_string(), "failed to write image: disk full");
+    }
+
+    #[test]
+    fn write_nested() {
+        /// Encodes another image whenever written to
+        struct Nested(Vec<u8>);
+
+        impl io::Write for Nested {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let image = ImageView::new(1, 1, 1, &[1]).unwrap();
+                let tga = encode_tga_to_vec(image).map_err(io::Error::other)?;
+                self.0.extend_from_slice(&tga);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let image = ImageView::new(1, 1, 3, &[1, 2, 3]).unwrap();
+        let mut nested = Nested(Vec::new());
+        EncoderOptions::new()
+            .write_png_to_writer(&mut nested, image)
+            .expect("Failed to write PNG");
+        assert!(!nested.0.is_empty());
+
+        let mut count = 0;
+        stbi_write_bmp_to_func(
+            &mut |_| {
+                encode_bmp_to_vec(ImageView::new(1, 1, 1, &[1]).unwrap()).unwrap();
+                count += 1;
+            },
+            1,
+            1,
+            1,
+            &[1],
+        )
+        .expect("Failed to write BMP to func");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn write_callback_panic() {
+        let ret = std::panic::catch_unwind(|| {
+            let _ = stbi_write_tga_to_func(&mut |_| panic!("callback panic"), 1, 1, 1, &[1]);
+        });
+        assert!(ret.is_err());
+
+        // Encoders keep working after a panic
+        let image = ImageView::new(1, 1, 1, &[1]).unwrap();
+        assert!(encode_tga_to_vec(image).is_ok());
+    }
+
+    #[test]
+    fn write_hdr_callback() {
+        let mut func_data = Vec::new();
+        stbi_write_hdr_to_func(
+            &mut |data| func_data.extend_from_slice(data),
+            2,
+            1,
+            3,
+            &[1.0; 6],
+        )
+        .expect("Failed to write HDR to func");
+
+        let image = ImageView::new(2, 1, 3, &[1.0; 6]).unwrap();
+        let vec = encode_hdr_to_vec(image).expect("Failed to encode HDR");
+        assert_eq!(func_data, vec);
+        assert!(vec.starts_with(b"#?RADIANCE"));
+    }
+
+    #[test]
+    fn write_hdr_callback_bytes() {
+        let mut func_data = Vec::new();
+        stbi_write_hdr_to_func(
+            &mut |data| func_data.extend_from_slice(data),
+            2,
+            1,
+            3,
+            &[1.0, 1.0, 1.0, 0.5, 0.25, 0.0],
+        )
+        .expect("Failed to write HDR to func");
+
+        // Header followed by uncompressed RGBE pixels (scanlines shorter than 8 aren't RLE encoded)
+        let mut expected = b"#?RADIANCE\n# Written by stb_image_write.h\nFORMAT=32-bit_rle_rgbe\n\
+            EXPOSURE=          1.0000000000000\n\n-Y 1 +X 2\n"
+            .to_vec();
+        expected.extend_from_slice(&[128, 128, 128, 129, 128, 64, 0, 128]);
+
+        assert_eq!(func_data, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "stb_image")]
+    fn encode_to_vec_roundtrip() {
+        use crate::image::{stbi_load_from_memory, Channels};
+
+        // 2x2 RGB image with 2 bytes of padding per row
+        let pixels = [255, 0, 0, 0, 255, 0, 7, 7, 0, 0, 255, 255, 255, 255];
+        let packed = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let image = ImageView::with_stride(2, 2, 3, 8, &pixels).unwrap();
+
+        let png = encode_png_to_vec(image).expect("Failed to encode PNG");
+        let (_, data) = stbi_load_from_memory(&png, Channels::Default).unwrap();
+        assert_eq!(data.as_slice(), packed);
+
+        // Decoded image can be written back as is
+        let tga = encode_tga_to_vec(data.view().unwrap()).expect("Failed to encode TGA");
+        let (_, data) = stbi_load_from_memory(&tga, Channels::Default).unwrap();
+        assert_eq!(data.as_slice(), packed);
+
+        for encoded in [
+            encode_bmp_to_vec(image).expect("Failed to encode BMP"),
+            encode_jpg_to_vec(image, 90).expect("Failed to encode JPEG"),
+        ]
+        .iter()
+        {
+            let (info, _) = stbi_load_from_memory(encoded, Channels::Default).unwrap();
+            assert_eq!((info.width, info.height), (2, 2));
+        }
+    }
+
+    #[test]
+    fn validate_buffer() {
+        // 2x2 RGB image with 8 bytes stride, last row doesn't need padding
+        let buffer = [0_u8; 14];
+        stbi_write_png_to_func(&mut |_| {}, 2, 2, 3, &buffer, 8)
+            .expect("Failed to write PNG with stride");
+
+        let err = stbi_write_png_to_func(&mut |_| {}, 2, 2, 3, &buffer[..13], 8).unwrap_err();
+        assert!(matches!(
+            err,
+            WriteError::BufferTooSmall {
+                required: 14,
+                actual: 13
+            }
+        ));
+
+        let err = stbi_write_png_to_func(&mut |_| {}, 2, 2, 3, &buffer, 5).unwrap_err();
+        assert!(matches!(err, WriteError::InvalidArgument(_)));
+
+        let err = stbi_write_tga_to_func(&mut |_| {}, 2, 2, 3, &buffer[..11]).unwrap_err();
+        assert!(matches!(
+            err,
+            WriteError::BufferTooSmall {
+                required: 12,
+                actual: 11
+            }
+        ));
+
+        let err = stbi_write_hdr_to_func(&mut |_| {}, 1, 1, 3, &[1.0; 2]).unwrap_err();
+        assert!(matches!(err, WriteError::BufferTooSmall { .. }));
+
+        let err = stbi_write_bmp_to_func(&mut |_| {}, i32::MAX, 2, 1, &[]).unwrap_err();
+        assert!(matches!(err, WriteError::InvalidArgument(_)));
+
+        let err = stbi_write_jpg_to_func(&mut |_| {}, 2, 2, 0, &buffer, 90).unwrap_err();
+        assert!(matches!(err, WriteError::InvalidArgument(_)));
+
+        let mut path = std::env::temp_dir();
+        path.push("stb_validate_buffer.bmp");
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        let err = stbi_write_bmp(&path, 4, 4, 1, &buffer).unwrap_err();
+        assert!(matches!(err, WriteError::BufferTooSmall { .. }));
+    }
+
+    #[test]
+    fn encoder_options_tga_rle() {
+        let pixels = [7_u8; 16 * 16];
+        let image = ImageView::new(16, 16, 1, &pixels).unwrap();
+
+        let mut rle = Vec::new();
+        EncoderOptions::new()
+            .write_tga_to_writer(&mut rle, image)
+            .expect("Failed to write TGA");
+
+        let mut raw = Vec::new();
+        EncoderOptions::new()
+            .tga_rle(false)
+            .write_tga_to_writer(&mut raw, image)
+            .expect("Failed to write TGA");
+
+        assert!(rle.len() < raw.len());
+        // Defaults are restored for functions not taking options
+        assert_eq!(encode_tga_to_vec(image).unwrap(), rle);
+    }
+
+    #[test]
+    fn encoder_options_png() {
+        let pixels: Vec<u8> = (0..64).map(|i| (i * 4) as u8).collect();
+        let image = ImageView::new(8, 8, 1, &pixels).unwrap();
+
+        let mut paeth = Vec::new();
+        EncoderOptions::new()
+            .png_compression_level(9)
+            .png_filter(Some(PngFilter::Paeth))
+            .write_png_to_writer(&mut paeth, image)
+            .expect("Failed to write PNG");
+
+        let mut unfiltered = Vec::new();
+        EncoderOptions::new()
+            .png_filter(Some(PngFilter::None))
+            .write_png_to_writer(&mut unfiltered, image)
+            .expect("Failed to write PNG");
+
+        assert_ne!(paeth, unfiltered);
+    }
+
+    #[test]
+    #[cfg(feature = "stb_image")]
+    fn encoder_options_flip_concurrently() {
+        use crate::image::{stbi_load_from_memory, Channels};
+        use std::thread;
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                thread::spawn(move || {
+                    let flip = i % 2 == 1;
+                    let pixels = [1_u8, 2, 3, 4];
+                    let image = ImageView::new(2, 2, 1, &pixels).unwrap();
+
+                    for _ in 0..32 {
+                        let mut png = Vec::new();
+                        EncoderOptions::new()
+                            .flip_vertically(flip)
+                            .write_png_to_writer(&mut png, image)
+                            .expect("Failed to write PNG");
+
+                        let (_, data) = stbi_load_from_memory(&png, Channels::Default).unwrap();
+                        let expected = if flip { [3, 4, 1, 2] } else { pixels };
+                        assert_eq!(data.as_slice(), expected);
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().expect("Writer thread panicked");
+        }
+    }
 }
@@ -28,6 +28,9 @@ pub mod easy_font;
 #[cfg(feature = "stb_dxt")]
 pub mod dxt;
 
+/// Borrowed image pixels shared by image loading, writing, resizing and DXT compression
+pub mod image_view;
+
 /// Image loading/decoding
 #[cfg(feature = "stb_image")]
 pub mod image;
@@ -35,3 +38,15 @@ pub mod image;
 /// Image writing to disk: PNG, TGA, BMP
 #[cfg(feature = "stb_image_write")]
 pub mod image_write;
+
+/// Parse, decode, and rasterize characters from truetype fonts
+#[cfg(feature = "stb_truetype")]
+pub mod truetype;
+
+/// Simple rectangle packer with decent quality
+#[cfg(feature = "stb_rect_pack")]
+pub mod rect_pack;
+
+/// Image resizing with decent quality
+#[cfg(feature = "stb_image_resize")]
+pub mod image_resize;
@@ -0,0 +1,707 @@
+//! Rust API for rectangle packing, useful for e.g. packing rectangular textures into an atlas.
+//! See https://github.com/nothings/stb/blob/master/stb_rect_pack.h
+//!
+//! Uses the Skyline Bottom-Left algorithm (or optionally Skyline Best-Fit).
+//! Does not do rotation (see `MultiPacker` for that).
+//!
+//! Rust implementation notes:
+//!
+//! - `Packer` owns both `stbrp_context` and the node storage it needs, so there is no need
+//!   to keep them alive manually.
+//! - Packing can be done in multiple steps by calling `Packer::pack` repeatedly, although packing
+//!   all rectangles at once produces better results.
+//! - `MultiPacker` spills rectangles into additional pages once a page is full, and adds padding
+//!   and rotation support on top of stb.
+
+use std::os::raw;
+
+use stb_sys as sys;
+
+/// Packing heuristic
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Heuristic {
+    /// Skyline Bottom-Left, rectangles sorted by height (default)
+    #[default]
+    SkylineBottomLeft = 0,
+    /// Skyline Best-Fit, rectangles sorted by height
+    SkylineBestFit = 1,
+}
+
+/// Rectangle to pack
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    /// Reserved for your use, returned back in `PackedRect`
+    pub id: i32,
+    /// Rectangle width
+    pub w: i32,
+    /// Rectangle height
+    pub h: i32,
+}
+
+impl Rect {
+    pub fn new(id: i32, w: i32, h: i32) -> Self {
+        Rect { id, w, h }
+    }
+}
+
+/// Rectangle with assigned location
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PackedRect {
+    /// User ID of the original `Rect`
+    pub id: i32,
+    /// Minimal location on X axis
+    pub x: i32,
+    /// Minimal location on Y axis (top-left if you imagine y increasing downwards)
+    pub y: i32,
+    /// Rectangle width
+    pub w: i32,
+    /// Rectangle height
+    pub h: i32,
+}
+
+/// Maximum target width and height accepted by `Packer`, stb needs a node per target column
+pub const MAX_SIZE: i32 = 1 << 20;
+
+/// Rectangle packer, wraps `stbrp_context` together with its temporary node storage.
+pub struct Packer {
+    // `stbrp_context` points to its own `extra` nodes, so it must not move once initialized
+    context: Box<sys::stbrp_context>,
+    nodes: Vec<sys::stbrp_node>,
+    heuristic: Heuristic,
+}
+
+impl Packer {
+    /// Creates a packer for a target of `width` x `height`, using the default heuristic.
+    /// Returns `None` if `width` or `height` is not in `1..=MAX_SIZE` range.
+    pub fn new(width: i32, height: i32) -> Option<Self> {
+        Self::with_heuristic(width, height, Heuristic::default())
+    }
+
+    /// Creates a packer for a target of `width` x `height`, using the given heuristic.
+    /// Returns `None` if `width` or `height` is not in `1..=MAX_SIZE` range.
+    pub fn with_heuristic(width: i32, height: i32, heuristic: Heuristic) -> Option<Self> {
+        let valid = 1..=MAX_SIZE;
+        if !valid.contains(&width) || !valid.contains(&height) {
+            return None;
+        }
+
+        let node = sys::stbrp_node {
+            x: 0,
+            y: 0,
+            next: std::ptr::null_mut(),
+        };
+
+        let mut packer = Packer {
+            context: Box::new(sys::stbrp_context {
+                width: 0,
+                height: 0,
+                align: 0,
+                init_mode: 0,
+                heuristic: 0,
+                num_nodes: 0,
+                active_head: std::ptr::null_mut(),
+                free_head: std::ptr::null_mut(),
+                extra: [node; 2],
+            }),
+            // Using `width` nodes guarantees the best results
+            nodes: vec![node; width as usize],
+            heuristic,
+        };
+
+        packer.init(width, height);
+        Some(packer)
+    }
+
+    /// Target width
+    pub fn width(&self) -> i32 {
+        self.context.width
+    }
+
+    /// Target height
+    pub fn height(&self) -> i32 {
+        self.context.height
+    }
+
+    /// Heuristic in use
+    pub fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    /// Forgets all packed rectangles and starts packing into an empty target again
+    pub fn reset(&mut self) {
+        self.init(self.context.width, self.context.height);
+    }
+
+    /// Assigns packed locations to rectangles.
+    /// Returns placed rectangles and rectangles which didn't fit, both in the original order.
+    /// Rectangles with zero width or height are placed at `(0, 0)` and take no space.
+    ///
+    /// The packer remembers placed rectangles, so calling this function again continues
+    /// packing into the same target.
+    pub fn pack(&mut self, rects: Vec<Rect>) -> (Vec<PackedRect>, Vec<Rect>) {
+        let is_valid = |r: &Rect| r.w >= 0 && r.h >= 0;
+
+        let mut sys_rects = rects
+            .iter()
+            .filter(|r| is_valid(r))
+            .map(|r| sys::stbrp_rect {
+                id: r.id,
+                w: r.w,
+                h: r.h,
+                x: 0,
+                y: 0,
+                was_packed: 0,
+            })
+            .collect::<Vec<_>>();
+
+        if !sys_rects.is_empty() {
+            unsafe {
+                sys::stbrp_pack_rects(
+                    self.context.as_mut(),
+                    sys_rects.as_mut_ptr(),
+                    sys_rects.len() as raw::c_int,
+                )
+            };
+        }
+
+        // Invalid rectangles have no stb counterpart, walk the input to keep the original order
+        let mut packed = Vec::with_capacity(sys_rects.len());
+        let mut unpacked = Vec::new();
+        let mut sys_rects = sys_rects.into_iter();
+        for rect in rects {
+            let sys_rect = if is_valid(&rect) {
+                sys_rects.next()
+            } else {
+                None
+            };
+
+            match sys_rect {
+                Some(sys_rect) if sys_rect.was_packed != 0 => packed.push(PackedRect {
+                    id: rect.id,
+                    x: sys_rect.x,
+                    y: sys_rect.y,
+                    w: rect.w,
+                    h: rect.h,
+                }),
+                _ => unpacked.push(rect),
+            }
+        }
+
+        (packed, unpacked)
+    }
+
+    fn init(&mut self, width: i32, height: i32) {
+        unsafe {
+            sys::stbrp_init_target(
+                self.context.as_mut(),
+                width,
+                height,
+                self.nodes.as_mut_ptr(),
+                self.nodes.len() as raw::c_int,
+            );
+            sys::stbrp_setup_heuristic(self.context.as_mut(), self.heuristic as raw::c_int);
+        }
+    }
+}
+
+/// Rectangle placed by `MultiPacker`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PagedRect {
+    /// User ID of the original `Rect`
+    pub id: i32,
+    /// Index of the page the rectangle is placed on
+    pub page: usize,
+    /// Minimal location on X axis
+    pub x: i32,
+    /// Minimal location on Y axis
+    pub y: i32,
+    /// Placed width (original height if rotated)
+    pub w: i32,
+    /// Placed height (original width if rotated)
+    pub h: i32,
+    /// Whether the rectangle has been rotated by 90 degrees to fit
+    pub rotated: bool,
+}
+
+/// Page usage statistics
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PageStats {
+    /// Number of rectangles placed on the page
+    pub rects: usize,
+    /// Total area of placed rectangles (excluding padding)
+    pub used_area: i64,
+    /// Ratio of `used_area` to the page area, in 0..1 range
+    pub occupancy: f32,
+}
+
+struct Page {
+    packer: Packer,
+    /// Padding the page has been created with, its packer is larger by this amount
+    padding: i32,
+    rects: usize,
+    used_area: i64,
+}
+
+/// Packs rectangles into as many pages of the same size as needed.
+///
+/// Rectangles can be added over time, each `pack` call first tries to fill existing pages
+/// and spills the rest into new pages.
+pub struct MultiPacker {
+    width: i32,
+    height: i32,
+    padding: i32,
+    allow_rotation: bool,
+    heuristic: Heuristic,
+    max_pages: Option<usize>,
+    pages: Vec<Page>,
+}
+
+impl MultiPacker {
+    /// Creates a packer with pages of `width` x `height`.
+    /// Nothing is packed if the page size with padding is not in `1..=MAX_SIZE` range.
+    pub fn new(width: i32, height: i32) -> Self {
+        MultiPacker {
+            width: width.max(0),
+            height: height.max(0),
+            padding: 0,
+            allow_rotation: false,
+            heuristic: Heuristic::default(),
+            max_pages: None,
+            pages: Vec::new(),
+        }
+    }
+
+    /// Sets the amount of empty space between rectangles (0 by default).
+    /// Should be set before packing, pages which already exist keep their padding.
+    pub fn padding(&mut self, padding: i32) -> &mut Self {
+        self.padding = padding.max(0);
+        self
+    }
+
+    /// Allows rotating rectangles by 90 degrees if they don't fit otherwise
+    pub fn allow_rotation(&mut self, allow: bool) -> &mut Self {
+        self.allow_rotation = allow;
+        self
+    }
+
+    /// Sets packing heuristic for new pages
+    pub fn heuristic(&mut self, heuristic: Heuristic) -> &mut Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    /// Limits the number of pages, unlimited by default
+    pub fn max_pages(&mut self, max_pages: usize) -> &mut Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Page width
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Page height
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Returns the number of pages in use
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns usage statistics of a page
+    pub fn page_stats(&self, page: usize) -> Option<PageStats> {
+        let page = self.pages.get(page)?;
+        let area = self.width as i64 * self.height as i64;
+
+        Some(PageStats {
+            rects: page.rects,
+            used_area: page.used_area,
+            occupancy: if area == 0 {
+                0.0
+            } else {
+                page.used_area as f32 / area as f32
+            },
+        })
+    }
+
+    /// Removes all pages
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Assigns pages and locations to rectangles, adding new pages when the existing ones are full.
+    /// Returns placed rectangles and rectangles which didn't fit (either bigger than a page or
+    /// the page limit has been reached), both in the original order.
+    pub fn pack(&mut self, rects: Vec<Rect>) -> (Vec<PagedRect>, Vec<Rect>) {
+        let mut placed = Vec::with_capacity(rects.len());
+        let mut pending = (0..rects.len()).collect::<Vec<_>>();
+
+        for page in 0..self.pages.len() {
+            if pending.is_empty() {
+                break;
+            }
+            pending = self.pack_page(page, &rects, pending, &mut placed);
+        }
+
+        while !pending.is_empty() && !matches!(self.max_pages, Some(max) if self.pages.len() >= max)
+        {
+            let packer = self.width.checked_add(self.padding).and_then(|width| {
+                let height = self.height.checked_add(self.padding)?;
+                Packer::with_heuristic(width, height, self.heuristic)
+            });
+            let packer = match packer {
+                Some(packer) => packer,
+                None => break,
+            };
+
+            self.pages.push(Page {
+                packer,
+                padding: self.padding,
+                rects: 0,
+                used_area: 0,
+            });
+
+            let count = pending.len();
+            pending = self.pack_page(self.pages.len() - 1, &rects, pending, &mut placed);
+
+            if pending.len() == count {
+                // Remaining rectangles don't fit even into an empty page
+                self.pages.pop();
+                break;
+            }
+        }
+
+        placed.sort_by_key(|(index, _)| *index);
+
+        (
+            placed.into_iter().map(|(_, rect)| rect).collect(),
+            pending.into_iter().map(|index| rects[index]).collect(),
+        )
+    }
+
+    /// Packs `pending` rectangles into the page, returns indices of rectangles which didn't fit
+    fn pack_page(
+        &mut self,
+        page: usize,
+        rects: &[Rect],
+        pending: Vec<usize>,
+        placed: &mut Vec<(usize, PagedRect)>,
+    ) -> Vec<usize> {
+        let mut pending = self.pack_into(page, rects, pending, false, placed);
+
+        if self.allow_rotation && !pending.is_empty() {
+            let (square, rotatable): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|i| rects[*i].w == rects[*i].h);
+
+            pending = self.pack_into(page, rects, rotatable, true, placed);
+            pending.extend(square);
+            pending.sort_unstable();
+        }
+
+        pending
+    }
+
+    fn pack_into(
+        &mut self,
+        page: usize,
+        rects: &[Rect],
+        pending: Vec<usize>,
+        rotated: bool,
+        placed: &mut Vec<(usize, PagedRect)>,
+    ) -> Vec<usize> {
+        let padding = self.pages[page].padding;
+        let size = |rect: &Rect| {
+            if rotated {
+                (rect.h, rect.w)
+            } else {
+                (rect.w, rect.h)
+            }
+        };
+
+        // Use indices as IDs, since user IDs are not guaranteed to be unique.
+        // Rectangles too big to add padding to can't fit and are left unpacked.
+        let mut oversized = Vec::new();
+        let input = pending
+            .into_iter()
+            .filter_map(|index| {
+                let (w, h) = size(&rects[index]);
+                if w <= 0 || h <= 0 {
+                    return Some(Rect::new(index as i32, w, h));
+                }
+
+                match (w.checked_add(padding), h.checked_add(padding)) {
+                    (Some(w), Some(h)) => Some(Rect::new(index as i32, w, h)),
+                    _ => {
+                        oversized.push(index);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let target = &mut self.pages[page];
+        let (packed, unpacked) = target.packer.pack(input);
+
+        for rect in packed {
+            let index = rect.id as usize;
+            let (w, h) = size(&rects[index]);
+
+            target.rects += 1;
+            target.used_area += w as i64 * h as i64;
+
+            placed.push((
+                index,
+                PagedRect {
+                    id: rects[index].id,
+                    page,
+                    x: rect.x,
+                    y: rect.y,
+                    w,
+                    h,
+                    rotated,
+                },
+            ));
+        }
+
+        let mut unpacked = unpacked
+            .into_iter()
+            .map(|rect| rect.id as usize)
+            .chain(oversized)
+            .collect::<Vec<_>>();
+        unpacked.sort_unstable();
+        unpacked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: &PackedRect, b: &PackedRect) -> bool {
+        a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+    }
+
+    #[test]
+    fn pack_all() {
+        let mut packer = Packer::new(20, 20).unwrap();
+        let rects = (0..4).map(|id| Rect::new(id, 10, 10)).collect();
+
+        let (packed, unpacked) = packer.pack(rects);
+        assert_eq!(packed.len(), 4);
+        assert!(unpacked.is_empty());
+
+        for (i, a) in packed.iter().enumerate() {
+            assert_eq!(a.id, i as i32);
+            assert!(a.x + a.w <= 20 && a.y + a.h <= 20);
+            for b in &packed[i + 1..] {
+                assert!(!overlaps(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn pack_partial() {
+        let mut packer = Packer::with_heuristic(20, 20, Heuristic::SkylineBestFit).unwrap();
+        let rects = vec![
+            Rect::new(1, 15, 15),
+            Rect::new(2, 30, 5),
+            Rect::new(3, 10, 10),
+        ];
+
+        let (packed, unpacked) = packer.pack(rects);
+        assert_eq!(packed.len(), 1);
+        assert_eq!(packed[0].id, 1);
+        assert_eq!(
+            unpacked.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn pack_incremental() {
+        let mut packer = Packer::new(10, 10).unwrap();
+
+        let (packed, _) = packer.pack(vec![Rect::new(1, 10, 10)]);
+        assert_eq!(packed.len(), 1);
+
+        let (packed, unpacked) = packer.pack(vec![Rect::new(2, 1, 1)]);
+        assert!(packed.is_empty());
+        assert_eq!(unpacked.len(), 1);
+
+        packer.reset();
+        let (packed, _) = packer.pack(vec![Rect::new(2, 1, 1)]);
+        assert_eq!(
+            packed[0],
+            PackedRect {
+                id: 2,
+                x: 0,
+                y: 0,
+                w: 1,
+                h: 1
+            }
+        );
+    }
+
+    #[test]
+    fn pack_invalid() {
+        let mut packer = Packer::new(10, 10).unwrap();
+        let (packed, unpacked) = packer.pack(vec![Rect::new(1, -1, 5), Rect::new(2, 0, 0)]);
+
+        assert_eq!(packed.len(), 1);
+        assert_eq!(packed[0].id, 2);
+        assert_eq!(unpacked[0].id, 1);
+    }
+
+    #[test]
+    fn pack_invalid_keeps_order() {
+        let mut packer = Packer::new(10, 10).unwrap();
+        let rects = vec![
+            Rect::new(1, 5, 10),
+            Rect::new(2, 20, 1),
+            Rect::new(3, -1, 1),
+            Rect::new(4, 5, 10),
+            Rect::new(5, 1, 1),
+        ];
+        let (packed, unpacked) = packer.pack(rects);
+
+        assert_eq!(packed.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(
+            unpacked.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![2, 3, 5]
+        );
+    }
+
+    #[test]
+    fn packer_invalid_size() {
+        assert!(Packer::new(0, 10).is_none());
+        assert!(Packer::new(10, -1).is_none());
+        assert!(Packer::new(MAX_SIZE + 1, 10).is_none());
+        assert!(Packer::with_heuristic(i32::MAX, i32::MAX, Heuristic::SkylineBestFit).is_none());
+        assert!(Packer::new(1, MAX_SIZE).is_some());
+    }
+
+    #[test]
+    fn multi_pack_pages() {
+        let mut packer = MultiPacker::new(20, 20);
+        let rects = (0..6).map(|id| Rect::new(id, 10, 10)).collect();
+
+        let (placed, unpacked) = packer.pack(rects);
+        assert!(unpacked.is_empty());
+        assert_eq!(packer.page_count(), 2);
+        assert_eq!(placed.iter().filter(|r| r.page == 0).count(), 4);
+        assert_eq!(placed.iter().filter(|r| r.page == 1).count(), 2);
+        assert_eq!(
+            placed.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+
+        let stats = packer.page_stats(0).unwrap();
+        assert_eq!(stats.rects, 4);
+        assert_eq!(stats.occupancy, 1.0);
+        assert_eq!(packer.page_stats(1).unwrap().occupancy, 0.5);
+        assert!(packer.page_stats(2).is_none());
+
+        // Existing pages are filled first
+        let (placed, _) = packer.pack(vec![Rect::new(6, 10, 10)]);
+        assert_eq!(placed[0].page, 1);
+        assert_eq!(packer.page_count(), 2);
+    }
+
+    #[test]
+    fn multi_pack_max_pages() {
+        let mut packer = MultiPacker::new(10, 10);
+        packer.max_pages(2);
+
+        let rects = (0..3).map(|id| Rect::new(id, 10, 10)).collect();
+        let (placed, unpacked) = packer.pack(rects);
+        assert_eq!(placed.len(), 2);
+        assert_eq!(unpacked, vec![Rect::new(2, 10, 10)]);
+        assert_eq!(packer.page_count(), 2);
+    }
+
+    #[test]
+    fn multi_pack_too_big() {
+        let mut packer = MultiPacker::new(10, 10);
+
+        let (placed, unpacked) = packer.pack(vec![Rect::new(1, 11, 5), Rect::new(2, 5, 5)]);
+        assert_eq!(placed.len(), 1);
+        assert_eq!(unpacked.len(), 1);
+        assert_eq!(packer.page_count(), 1);
+    }
+
+    #[test]
+    fn multi_pack_padding() {
+        let mut packer = MultiPacker::new(21, 10);
+        packer.padding(1);
+
+        let (placed, _) = packer.pack(vec![Rect::new(1, 10, 10), Rect::new(2, 10, 10)]);
+        assert_eq!(packer.page_count(), 1);
+        assert_eq!((placed[0].x - placed[1].x).abs(), 11);
+
+        let mut packer = MultiPacker::new(20, 10);
+        packer.padding(1);
+
+        packer.pack(vec![Rect::new(1, 10, 10), Rect::new(2, 10, 10)]);
+        assert_eq!(packer.page_count(), 2);
+    }
+
+    #[test]
+    fn multi_pack_padding_change() {
+        let mut packer = MultiPacker::new(20, 10);
+        packer.padding(2);
+        packer.pack(vec![Rect::new(1, 10, 10)]);
+
+        // The first page keeps its padding, so there is no room left on it
+        packer.padding(0);
+        let (placed, _) = packer.pack(vec![Rect::new(2, 10, 10)]);
+        assert_eq!(placed[0].page, 1);
+        assert_eq!(packer.page_count(), 2);
+    }
+
+    #[test]
+    fn multi_pack_overflow() {
+        let mut packer = MultiPacker::new(10, 10);
+        packer.padding(1);
+
+        let rects = vec![Rect::new(1, i32::MAX, 1), Rect::new(2, 1, 1)];
+        let (placed, unpacked) = packer.pack(rects);
+        assert_eq!(placed.len(), 1);
+        assert_eq!(unpacked, vec![Rect::new(1, i32::MAX, 1)]);
+
+        let mut packer = MultiPacker::new(i32::MAX, 10);
+        packer.padding(1);
+
+        let (placed, unpacked) = packer.pack(vec![Rect::new(1, 1, 1)]);
+        assert!(placed.is_empty());
+        assert_eq!(unpacked.len(), 1);
+        assert_eq!(packer.page_count(), 0);
+    }
+
+    #[test]
+    fn multi_pack_rotation() {
+        let mut packer = MultiPacker::new(20, 10);
+
+        let (placed, unpacked) = packer.pack(vec![Rect::new(1, 5, 20)]);
+        assert!(placed.is_empty());
+        assert_eq!(unpacked.len(), 1);
+
+        packer.allow_rotation(true);
+        let (placed, _) = packer.pack(vec![Rect::new(1, 5, 20)]);
+        assert_eq!(
+            placed[0],
+            PagedRect {
+                id: 1,
+                page: 0,
+                x: 0,
+                y: 0,
+                w: 20,
+                h: 5,
+                rotated: true,
+            }
+        );
+    }
+}
@@ -0,0 +1,2037 @@
+//! Rust API for parsing, decoding and rasterizing characters from TrueType fonts.
+//! See https://github.com/nothings/stb/blob/master/stb_truetype.h
+//!
+//! - Parse files
+//! - Extract glyph metrics
+//! - Extract glyph shapes
+//! - Render glyphs to one-channel bitmaps with antialiasing (box filter)
+//! - Render glyphs to one-channel SDF bitmaps (signed-distance field/function)
+//! - Pack glyphs of multiple fonts into a texture atlas
+//!
+//! stb_truetype itself does no range checking of the offsets found in the file,
+//! meaning an attacker can use it to read arbitrary memory.
+//!
+//! Rust implementation notes:
+//!
+//! - `FontInfo::new` range checks everything stb reads from the font before handing it to stb:
+//!   the font directory, the required tables, the cmap subtable used for glyph lookup, glyph
+//!   locations and outlines, and kerning pairs. Kerning from the `GPOS` table is looked up
+//!   in Rust instead of stb.
+//! - Fonts with CFF (OTF) outlines can only be loaded with `unsafe` `FontInfo::new_unchecked`,
+//!   since stb doesn't range check CFF data.
+//! - `FontInfo` keeps the font bytes alive for as long as `stbtt_fontinfo` points into them,
+//!   the data can be either owned (`Vec<u8>`) or borrowed (`&[u8]`).
+//! - Glyph indices are checked against the number of glyphs in the font before being passed to
+//!   stb, functions taking an out of range glyph index return `None`.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::mem::MaybeUninit;
+use std::ops::Range;
+use std::os::raw;
+use std::ptr;
+use std::slice;
+
+use stb_sys as sys;
+
+/// Horizontal glyph metrics, expressed in unscaled coordinates
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct HMetrics {
+    /// Offset from the current horizontal position to the next horizontal position
+    pub advance_width: i32,
+    /// Offset from the current horizontal position to the left edge of the character
+    pub left_side_bearing: i32,
+}
+
+/// Vertical font metrics, expressed in unscaled coordinates
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct VMetrics {
+    /// Coordinate above the baseline the font extends
+    pub ascent: i32,
+    /// Coordinate below the baseline the font extends (i.e. it is typically negative)
+    pub descent: i32,
+    /// Spacing between one row's descent and the next row's ascent
+    pub line_gap: i32,
+}
+
+/// Axis aligned bounding box
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+impl BoundingBox {
+    /// Returns box width (`x1 - x0`)
+    pub fn width(&self) -> i32 {
+        self.x1 - self.x0
+    }
+
+    /// Returns box height (`y1 - y0`)
+    pub fn height(&self) -> i32 {
+        self.y1 - self.y0
+    }
+}
+
+/// One-channel glyph bitmap, 0..255 values laid out left-to-right, top-to-bottom
+/// with no padding between rows
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GlyphBitmap {
+    /// Bitmap width in pixels
+    pub width: i32,
+    /// Bitmap height in pixels
+    pub height: i32,
+    /// Horizontal offset in pixel space from the glyph origin to the left of the bitmap
+    pub xoff: i32,
+    /// Vertical offset in pixel space from the glyph origin to the top of the bitmap
+    pub yoff: i32,
+    /// Bitmap pixels (`width * height` bytes)
+    pub data: Vec<u8>,
+}
+
+impl GlyphBitmap {
+    /// Returns the number of pixels (which is width x height),
+    /// 0 if width or height is negative or the size overflows `usize`
+    pub fn size(&self) -> usize {
+        self.checked_size().unwrap_or(0)
+    }
+
+    fn checked_size(&self) -> Option<usize> {
+        usize::try_from(self.width)
+            .ok()?
+            .checked_mul(usize::try_from(self.height).ok()?)
+    }
+
+    /// Returns `true` if the bitmap has no pixels
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns pixel value at `x` and `y` or `None` if out of bounds
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u8> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.data.get((y * self.width + x) as usize).copied()
+    }
+}
+
+/// Parsed font, wraps `stbtt_fontinfo` together with the font data it points to.
+pub struct FontInfo<'a> {
+    info: sys::stbtt_fontinfo,
+    data: Cow<'a, [u8]>,
+    /// Location of the `GPOS` table, kerning pairs are read from it in Rust instead of stb
+    gpos: Option<Range<usize>>,
+}
+
+// `stbtt_fontinfo` only holds read-only pointers into `data`, which is owned or borrowed
+// immutably by `FontInfo`, and stb never mutates it after `stbtt_InitFont`.
+unsafe impl Send for FontInfo<'_> {}
+unsafe impl Sync for FontInfo<'_> {}
+
+impl<'a> FontInfo<'a> {
+    /// Parses the font with the given `index` from font file (TTF) or collection (TTC) data.
+    /// Use 0 as `index` for regular font files.
+    /// Returns `None` if the data doesn't contain a font with this index, it can't be parsed,
+    /// any of the tables or glyphs stb reads points outside of `data`, or the font has CFF (OTF)
+    /// outlines, which can only be loaded with `new_unchecked`.
+    pub fn new<D>(data: D, index: i32) -> Option<FontInfo<'a>>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        let data = data.into();
+        let offset = stbtt_get_font_offset_for_index(&data, index)?;
+        check_font(&data, offset as usize)?;
+
+        let gpos = find_table_range(&data, offset as usize, b"GPOS");
+        let mut font = unsafe { Self::init(data, offset)? };
+
+        // stb doesn't range check `GPOS` lookups, keep it from reading the table
+        font.info.gpos = 0;
+        font.gpos = gpos;
+        Some(font)
+    }
+
+    /// Same as `new`, but hands the font to stb without range checks, which also allows
+    /// loading fonts with CFF (OTF) outlines.
+    ///
+    /// # Safety
+    ///
+    /// stb doesn't range check the offsets found in the font, so `data` must be a well-formed
+    /// font from a trusted source. Malformed fonts make stb read out of bounds.
+    pub unsafe fn new_unchecked<D>(data: D, index: i32) -> Option<FontInfo<'a>>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        let data = data.into();
+        let offset = stbtt_get_font_offset_for_index(&data, index)?;
+        Self::init(data, offset)
+    }
+
+    unsafe fn init(data: Cow<'a, [u8]>, offset: i32) -> Option<FontInfo<'a>> {
+        let mut info = MaybeUninit::<sys::stbtt_fontinfo>::uninit();
+        let ret = sys::stbtt_InitFont(info.as_mut_ptr(), data.as_ptr(), offset);
+        if ret == 0 {
+            return None;
+        }
+
+        Some(FontInfo {
+            info: info.assume_init(),
+            data,
+            gpos: None,
+        })
+    }
+
+    /// Returns raw font data this font has been parsed from
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the number of glyphs in the font
+    pub fn num_glyphs(&self) -> i32 {
+        self.info.numGlyphs
+    }
+
+    /// Returns the glyph index of a codepoint or `None` if the character isn't present in the font.
+    /// Glyph indices are faster to use with the other APIs when the same character is used repeatedly.
+    pub fn find_glyph_index(&self, codepoint: char) -> Option<i32> {
+        let glyph = unsafe { sys::stbtt_FindGlyphIndex(&self.info, codepoint as raw::c_int) };
+        if glyph == 0 {
+            None
+        } else {
+            Some(glyph)
+        }
+    }
+
+    /// Computes a scale factor to produce a font whose "height" is `pixels` tall.
+    /// Height is measured as the distance from the highest ascender to the lowest descender.
+    pub fn scale_for_pixel_height(&self, pixels: f32) -> f32 {
+        unsafe { sys::stbtt_ScaleForPixelHeight(&self.info, pixels) }
+    }
+
+    /// Computes a scale factor to produce a font whose EM size is mapped to `pixels` tall.
+    /// This is probably what traditional APIs compute, but I'm not positive.
+    pub fn scale_for_mapping_em_to_pixels(&self, pixels: f32) -> f32 {
+        unsafe { sys::stbtt_ScaleForMappingEmToPixels(&self.info, pixels) }
+    }
+
+    /// Returns font vertical metrics.
+    /// You should advance the vertical position by `ascent - descent + line_gap`.
+    pub fn v_metrics(&self) -> VMetrics {
+        let mut metrics = VMetrics::default();
+        unsafe {
+            sys::stbtt_GetFontVMetrics(
+                &self.info,
+                &mut metrics.ascent,
+                &mut metrics.descent,
+                &mut metrics.line_gap,
+            )
+        };
+        metrics
+    }
+
+    /// Returns the bounding box around all possible characters
+    pub fn bounding_box(&self) -> BoundingBox {
+        let mut bbox = BoundingBox::default();
+        unsafe {
+            sys::stbtt_GetFontBoundingBox(
+                &self.info,
+                &mut bbox.x0,
+                &mut bbox.y0,
+                &mut bbox.x1,
+                &mut bbox.y1,
+            )
+        };
+        bbox
+    }
+
+    /// Returns horizontal metrics of a codepoint
+    pub fn codepoint_h_metrics(&self, codepoint: char) -> HMetrics {
+        let mut metrics = HMetrics::default();
+        unsafe {
+            sys::stbtt_GetCodepointHMetrics(
+                &self.info,
+                codepoint as raw::c_int,
+                &mut metrics.advance_width,
+                &mut metrics.left_side_bearing,
+            )
+        };
+        metrics
+    }
+
+    /// Returns horizontal metrics of a glyph
+    pub fn glyph_h_metrics(&self, glyph: i32) -> Option<HMetrics> {
+        if !self.is_valid_glyph(glyph) {
+            return None;
+        }
+
+        let mut metrics = HMetrics::default();
+        unsafe {
+            sys::stbtt_GetGlyphHMetrics(
+                &self.info,
+                glyph,
+                &mut metrics.advance_width,
+                &mut metrics.left_side_bearing,
+            )
+        };
+        Some(metrics)
+    }
+
+    /// Returns an additional amount to add to the advance value between `ch1` and `ch2`
+    pub fn codepoint_kern_advance(&self, ch1: char, ch2: char) -> i32 {
+        let glyph1 = self.find_glyph_index(ch1).unwrap_or(0);
+        let glyph2 = self.find_glyph_index(ch2).unwrap_or(0);
+        self.glyph_kern_advance(glyph1, glyph2).unwrap_or(0)
+    }
+
+    /// Returns an additional amount to add to the advance value between `glyph1` and `glyph2`
+    pub fn glyph_kern_advance(&self, glyph1: i32, glyph2: i32) -> Option<i32> {
+        if !self.is_valid_glyph(glyph1) || !self.is_valid_glyph(glyph2) {
+            return None;
+        }
+
+        match &self.gpos {
+            Some(gpos) => Some(gpos_kern_advance(&self.data[gpos.clone()], glyph1, glyph2)),
+            None => Some(unsafe { sys::stbtt_GetGlyphKernAdvance(&self.info, glyph1, glyph2) }),
+        }
+    }
+
+    /// Returns the bounding box of the visible part of the glyph, in unscaled coordinates.
+    /// Returns `None` if the glyph index is invalid or the glyph has no shape.
+    pub fn glyph_box(&self, glyph: i32) -> Option<BoundingBox> {
+        if !self.is_valid_glyph(glyph) {
+            return None;
+        }
+
+        let mut bbox = BoundingBox::default();
+        let ret = unsafe {
+            sys::stbtt_GetGlyphBox(
+                &self.info,
+                glyph,
+                &mut bbox.x0,
+                &mut bbox.y0,
+                &mut bbox.x1,
+                &mut bbox.y1,
+            )
+        };
+
+        if ret == 0 {
+            None
+        } else {
+            Some(bbox)
+        }
+    }
+
+    /// Returns `true` if nothing is drawn for this glyph (or the glyph index is invalid)
+    pub fn is_glyph_empty(&self, glyph: i32) -> bool {
+        if !self.is_valid_glyph(glyph) {
+            return true;
+        }
+
+        unsafe { sys::stbtt_IsGlyphEmpty(&self.info, glyph) != 0 }
+    }
+
+    /// Returns the bounding box of the bitmap centered around the glyph origin, so the bitmap
+    /// width is `x1 - x0`, height is `y1 - y0`, and location to place the bitmap top left is
+    /// `(left_side_bearing * scale, y0)`.
+    /// Note that the bitmap uses y-increases-down, but the shape uses y-increases-up.
+    pub fn glyph_bitmap_box(&self, glyph: i32, scale_x: f32, scale_y: f32) -> Option<BoundingBox> {
+        self.glyph_bitmap_box_subpixel(glyph, scale_x, scale_y, 0.0, 0.0)
+    }
+
+    /// Same as `glyph_bitmap_box`, but takes a fractional shift for subpixel positioning
+    pub fn glyph_bitmap_box_subpixel(
+        &self,
+        glyph: i32,
+        scale_x: f32,
+        scale_y: f32,
+        shift_x: f32,
+        shift_y: f32,
+    ) -> Option<BoundingBox> {
+        if !self.is_valid_glyph(glyph) {
+            return None;
+        }
+
+        let mut bbox = BoundingBox::default();
+        unsafe {
+            sys::stbtt_GetGlyphBitmapBoxSubpixel(
+                &self.info,
+                glyph,
+                scale_x,
+                scale_y,
+                shift_x,
+                shift_y,
+                &mut bbox.x0,
+                &mut bbox.y0,
+                &mut bbox.x1,
+                &mut bbox.y1,
+            )
+        };
+        Some(bbox)
+    }
+
+    /// Renders an antialiased coverage bitmap of the glyph, scaled by `scale_x` and `scale_y`.
+    /// If `scale_x` is 0, `scale_y` is used for both axes (and vice versa).
+    /// Returns `None` if the glyph index is invalid, the scale isn't positive, or the bitmap box
+    /// at this scale has more than `i32::MAX` pixels.
+    pub fn glyph_bitmap(&self, glyph: i32, scale_x: f32, scale_y: f32) -> Option<GlyphBitmap> {
+        self.glyph_bitmap_subpixel(glyph, scale_x, scale_y, 0.0, 0.0)
+    }
+
+    /// Same as `glyph_bitmap`, but takes a fractional shift for subpixel positioning
+    pub fn glyph_bitmap_subpixel(
+        &self,
+        glyph: i32,
+        scale_x: f32,
+        scale_y: f32,
+        shift_x: f32,
+        shift_y: f32,
+    ) -> Option<GlyphBitmap> {
+        let scale_x = if scale_x == 0.0 { scale_y } else { scale_x };
+        let scale_y = if scale_y == 0.0 { scale_x } else { scale_y };
+
+        if !self.is_valid_glyph(glyph)
+            || !self.is_valid_bitmap(glyph, scale_x, scale_y, shift_x, shift_y, 0)
+        {
+            return None;
+        }
+
+        let mut bitmap = GlyphBitmap::default();
+        let data = unsafe {
+            sys::stbtt_GetGlyphBitmapSubpixel(
+                &self.info,
+                scale_x,
+                scale_y,
+                shift_x,
+                shift_y,
+                glyph,
+                &mut bitmap.width,
+                &mut bitmap.height,
+                &mut bitmap.xoff,
+                &mut bitmap.yoff,
+            )
+        };
+
+        bitmap.data = unsafe { self.take_bitmap(data, bitmap.size(), sys::stbtt_FreeBitmap) };
+        Some(bitmap)
+    }
+
+    /// Computes a discretized signed distance field of the glyph, suitable for storing in a
+    /// single-channel texture, sampling with bilinear filtering, and testing against larger than
+    /// some threshold to produce scalable fonts.
+    ///
+    /// - `scale` controls the size of the resulting SDF bitmap, same as for `glyph_bitmap`
+    /// - `padding` adds extra "pixels" around the character which are filled with the distance
+    ///   to the character (not 0), which allows effects like bit outlines
+    /// - `onedge_value` is a value 0-255 to test the SDF against to reconstruct the character
+    /// - `pixel_dist_scale` is what value the SDF should increase by when moving one SDF "pixel"
+    ///   away from the edge (on the 0..255 scale), if positive, > `onedge_value` is inside;
+    ///   if negative, < `onedge_value` is inside
+    ///
+    /// Empty glyphs (like space) produce an empty bitmap.
+    /// Returns `None` if the glyph index is invalid, `scale` isn't positive, or the bitmap box
+    /// at this scale and padding is inverted or has more than `i32::MAX` pixels.
+    pub fn glyph_sdf(
+        &self,
+        glyph: i32,
+        scale: f32,
+        padding: i32,
+        onedge_value: u8,
+        pixel_dist_scale: f32,
+    ) -> Option<GlyphBitmap> {
+        if !self.is_valid_glyph(glyph)
+            || !self.is_valid_bitmap(glyph, scale, scale, 0.0, 0.0, padding)
+        {
+            return None;
+        }
+
+        let mut bitmap = GlyphBitmap::default();
+        let data = unsafe {
+            sys::stbtt_GetGlyphSDF(
+                &self.info,
+                scale,
+                glyph,
+                padding,
+                onedge_value,
+                pixel_dist_scale,
+                &mut bitmap.width,
+                &mut bitmap.height,
+                &mut bitmap.xoff,
+                &mut bitmap.yoff,
+            )
+        };
+
+        if data.is_null() {
+            // stb doesn't touch the output arguments for empty glyphs
+            return Some(GlyphBitmap::default());
+        }
+
+        bitmap.data = unsafe { self.take_bitmap(data, bitmap.size(), sys::stbtt_FreeSDF) };
+        Some(bitmap)
+    }
+
+    /// Copies bitmap memory allocated by stb into a vector and releases it with `free`
+    unsafe fn take_bitmap(
+        &self,
+        data: *mut raw::c_uchar,
+        size: usize,
+        free: unsafe extern "C" fn(*mut raw::c_uchar, *mut raw::c_void),
+    ) -> Vec<u8> {
+        if data.is_null() {
+            return Vec::new();
+        }
+
+        let vec = slice::from_raw_parts(data, size).to_vec();
+        free(data, self.info.userdata);
+        vec
+    }
+
+    fn is_valid_glyph(&self, glyph: i32) -> bool {
+        glyph >= 0 && glyph < self.info.numGlyphs
+    }
+
+    /// Returns `true` if the glyph bitmap stb allocates isn't inverted and has at most
+    /// `i32::MAX` pixels. stb doesn't check this and computes the bitmap size with `int`.
+    fn is_valid_bitmap(
+        &self,
+        glyph: i32,
+        scale_x: f32,
+        scale_y: f32,
+        shift_x: f32,
+        shift_y: f32,
+        padding: i32,
+    ) -> bool {
+        match self.bitmap_size(glyph, scale_x, scale_y, shift_x, shift_y, padding) {
+            Some((width, height)) if width >= 0 && height >= 0 => {
+                matches!(width.checked_mul(height), Some(size) if size <= i32::MAX as i64)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the size of the bitmap box computed the same way as in
+    /// `stbtt_GetGlyphBitmapBoxSubpixel`, grown by `padding` on each side unless it's empty.
+    /// Returns `None` if scale isn't positive (stb rasterizer asserts edges aren't flipped)
+    /// or box coordinates don't fit in `int`.
+    fn bitmap_size(
+        &self,
+        glyph: i32,
+        scale_x: f32,
+        scale_y: f32,
+        shift_x: f32,
+        shift_y: f32,
+        padding: i32,
+    ) -> Option<(i64, i64)> {
+        if scale_x <= 0.0 || scale_y <= 0.0 {
+            return None;
+        }
+
+        let glyph_box = match self.glyph_box(glyph) {
+            Some(glyph_box) => glyph_box,
+            None => return Some((0, 0)),
+        };
+
+        let x0 = (glyph_box.x0 as f32 * scale_x + shift_x).floor();
+        let y0 = (-glyph_box.y1 as f32 * scale_y + shift_y).floor();
+        let x1 = (glyph_box.x1 as f32 * scale_x + shift_x).ceil();
+        let y1 = (-glyph_box.y0 as f32 * scale_y + shift_y).ceil();
+
+        let coords = [x0, y0, x1, y1];
+        if !coords
+            .iter()
+            .all(|c| c.is_finite() && c.abs() <= MAX_BITMAP_COORD)
+        {
+            return None;
+        }
+
+        let width = x1 as i64 - x0 as i64;
+        let height = y1 as i64 - y0 as i64;
+        if width == 0 || height == 0 {
+            return Some((width, height));
+        }
+
+        let padding = 2 * padding as i64;
+        Some((width + padding, height + padding))
+    }
+}
+
+/// Largest bitmap box coordinate, stb converts them from `float` to `int`
+const MAX_BITMAP_COORD: f32 = (1 << 30) as f32;
+
+/// Minimal number of bytes needed to read the font directory header
+const FONT_HEADER_SIZE: usize = 12;
+
+/// Returns the number of fonts in a font collection (TTC) or 1 for regular font files.
+/// Returns `None` if the data isn't a font.
+pub fn stbtt_get_number_of_fonts(data: &[u8]) -> Option<i32> {
+    if data.len() < FONT_HEADER_SIZE {
+        return None;
+    }
+
+    let ret = unsafe { sys::stbtt_GetNumberOfFonts(data.as_ptr()) };
+    if ret <= 0 {
+        None
+    } else {
+        Some(ret)
+    }
+}
+
+/// Returns the offset of the font with the given index in a font collection (TTC).
+/// For regular font files the only valid index is 0.
+/// Returns `None` if the index is out of range.
+pub fn stbtt_get_font_offset_for_index(data: &[u8], index: i32) -> Option<i32> {
+    if data.len() < FONT_HEADER_SIZE || index < 0 {
+        return None;
+    }
+
+    // stb reads the offset from the collection header without checking its size
+    if data.starts_with(b"ttcf") {
+        read_u32(data, FONT_HEADER_SIZE + index as usize * 4)?;
+    }
+
+    let ret = unsafe { sys::stbtt_GetFontOffsetForIndex(data.as_ptr(), index) };
+    if ret < 0 {
+        None
+    } else {
+        Some(ret)
+    }
+}
+
+/// Size of a table record in the font directory
+const TABLE_RECORD_SIZE: usize = 16;
+
+/// Reads big endian `u16` at `offset`, returns `None` if out of bounds
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads big endian `u32` at `offset`, returns `None` if out of bounds
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Returns the bytes of the first table with `tag` in the font directory at `fontstart`,
+/// the same way `stbtt__find_table` looks tables up.
+/// Expects table records to be checked against the data length.
+fn find_table<'d>(data: &'d [u8], fontstart: usize, tag: &[u8; 4]) -> Option<&'d [u8]> {
+    data.get(find_table_range(data, fontstart, tag)?)
+}
+
+/// Returns the location of the first table with `tag` in the font directory at `fontstart`
+fn find_table_range(data: &[u8], fontstart: usize, tag: &[u8; 4]) -> Option<Range<usize>> {
+    let num_tables = read_u16(data, fontstart + 4)? as usize;
+
+    (0..num_tables)
+        .map(|i| fontstart + FONT_HEADER_SIZE + i * TABLE_RECORD_SIZE)
+        .find(|&record| &data[record..record + 4] == tag)
+        .and_then(|record| table_range(data, record))
+}
+
+/// Returns the offset and length of the table record at `record` as a range
+fn table_range(data: &[u8], record: usize) -> Option<Range<usize>> {
+    let offset = read_u32(data, record + 8)? as usize;
+    let length = read_u32(data, record + 12)? as usize;
+
+    Some(offset..offset.checked_add(length)?)
+}
+
+/// Range checks everything stb reads from the font at `fontstart` without checking: the font
+/// directory, the fixed size fields of the required tables, the cmap subtable used for glyph
+/// lookup, horizontal metrics, glyph locations and outlines, and kerning pairs.
+/// Fonts with CFF outlines are rejected, stb doesn't range check them either.
+fn check_font(data: &[u8], fontstart: usize) -> Option<()> {
+    let num_tables = read_u16(data, fontstart.checked_add(4)?)? as usize;
+    let records_end = fontstart + FONT_HEADER_SIZE + num_tables * TABLE_RECORD_SIZE;
+    if records_end > data.len() {
+        return None;
+    }
+
+    for i in 0..num_tables {
+        let range = table_range(data, fontstart + FONT_HEADER_SIZE + i * TABLE_RECORD_SIZE)?;
+        if range.end > data.len() {
+            return None;
+        }
+    }
+
+    let head = find_table(data, fontstart, b"head")?;
+    let hhea = find_table(data, fontstart, b"hhea")?;
+    let maxp = find_table(data, fontstart, b"maxp")?;
+    let num_glyphs = read_u16(maxp, 4)? as usize;
+    if head.len() < HEAD_SIZE {
+        return None;
+    }
+
+    let cmap = find_table(data, fontstart, b"cmap")?;
+    check_cmap(cmap, num_glyphs)?;
+
+    // `numOfLongHorMetrics` advance/bearing pairs followed by bearings of the remaining glyphs
+    let hmtx = find_table(data, fontstart, b"hmtx")?;
+    let num_long_metrics = read_u16(hhea, 34)? as usize;
+    let hmtx_size = num_long_metrics * 4 + num_glyphs.saturating_sub(num_long_metrics) * 2;
+    if num_long_metrics == 0 || hmtx.len() < hmtx_size {
+        return None;
+    }
+
+    let glyf = find_table(data, fontstart, b"glyf")?;
+    let loca = find_table(data, fontstart, b"loca")?;
+    match read_u16(head, 50)? {
+        0 => check_glyphs(glyf, num_glyphs, |glyph| {
+            read_u16(loca, glyph * 2).map(|offset| offset as usize * 2)
+        })?,
+        1 => check_glyphs(glyf, num_glyphs, |glyph| {
+            read_u32(loca, glyph * 4).map(|offset| offset as usize)
+        })?,
+        // stb treats glyphs of unknown location formats as empty
+        _ => {}
+    }
+
+    // stb only uses the first subtable of horizontal kerning pairs
+    if let Some(kern) = find_table(data, fontstart, b"kern") {
+        if read_u16(kern, 2)? >= 1 && read_u16(kern, 8)? == 1 {
+            let num_pairs = read_u16(kern, 10)? as usize;
+            if kern.len() < 18 + num_pairs * 6 {
+                return None;
+            }
+        }
+    }
+
+    Some(())
+}
+
+/// Size of the `head` table
+const HEAD_SIZE: usize = 54;
+
+/// Checks the cmap subtable picked by `stbtt_InitFont`: every lookup `stbtt_FindGlyphIndex`
+/// can do has to stay inside the table and return a glyph below `num_glyphs`.
+fn check_cmap(cmap: &[u8], num_glyphs: usize) -> Option<()> {
+    let num_encodings = read_u16(cmap, 2)? as usize;
+
+    // stb uses the last Unicode encoding it finds
+    let mut index_map = None;
+    for i in 0..num_encodings {
+        let record = 4 + i * 8;
+        let platform = read_u16(cmap, record)?;
+        let encoding = read_u16(cmap, record + 2)?;
+        if platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10)) {
+            index_map = Some(read_u32(cmap, record + 4)? as usize);
+        }
+    }
+
+    // stb fails to parse fonts without a Unicode encoding
+    let subtable = match index_map {
+        Some(offset) => cmap.get(offset..)?,
+        None => return Some(()),
+    };
+
+    let glyphs: Box<dyn Iterator<Item = Option<u64>>> = match read_u16(subtable, 0)? {
+        0 => {
+            let bytes = (read_u16(subtable, 2)? as usize).saturating_sub(6);
+            let glyphs = subtable.get(6..6 + bytes)?;
+            Box::new(glyphs.iter().map(|&glyph| Some(glyph as u64)))
+        }
+        4 => Box::new(
+            (0..=0xffff)
+                .map(move |codepoint| cmap_format4_lookup(subtable, codepoint).map(u64::from)),
+        ),
+        6 => {
+            let count = read_u16(subtable, 8)? as usize;
+            Box::new((0..count).map(move |i| read_u16(subtable, 10 + i * 2).map(u64::from)))
+        }
+        format @ 12 | format @ 13 => {
+            let num_groups = read_u32(subtable, 12)? as usize;
+            subtable.get(16..16_usize.checked_add(num_groups.checked_mul(12)?)?)?;
+
+            Box::new((0..num_groups).filter_map(move |i| {
+                let group = 16 + i * 12;
+                let start = read_u32(subtable, group)? as u64;
+                let end = read_u32(subtable, group + 4)? as u64;
+                let start_glyph = read_u32(subtable, group + 8)? as u64;
+
+                // Only codepoints a `char` can hold are ever looked up
+                let end = end.min(char::MAX as u64);
+                match format {
+                    12 if start <= end => Some(Some(start_glyph + (end - start))),
+                    12 => None,
+                    _ => Some(Some(start_glyph)),
+                }
+            }))
+        }
+        // stb asserts on other formats
+        _ => return None,
+    };
+
+    for glyph in glyphs {
+        let glyph = glyph?;
+        if glyph != 0 && glyph >= num_glyphs as u64 {
+            return None;
+        }
+    }
+
+    Some(())
+}
+
+/// Looks `codepoint` up in a format 4 cmap subtable the same way `stbtt_FindGlyphIndex` does,
+/// returns `None` if any of the values it reads is out of bounds
+fn cmap_format4_lookup(subtable: &[u8], codepoint: u32) -> Option<u16> {
+    let seg_count = read_u16(subtable, 6)? as usize >> 1;
+    let mut search_range = read_u16(subtable, 8)? as usize >> 1;
+    let entry_selector = read_u16(subtable, 10)?;
+    let range_shift = read_u16(subtable, 12)? as usize >> 1;
+
+    // Binary search the segment end codes
+    let end_count = 14;
+    let mut search = end_count;
+    if codepoint >= read_u16(subtable, search + range_shift * 2)? as u32 {
+        search += range_shift * 2;
+    }
+
+    search -= 2;
+    for _ in 0..entry_selector {
+        search_range >>= 1;
+        if codepoint > read_u16(subtable, search + search_range * 2)? as u32 {
+            search += search_range * 2;
+        }
+
+        // stb keeps reading the same value once the range is down to 0
+        if search_range == 0 {
+            break;
+        }
+    }
+    search += 2;
+
+    let item = (search - end_count) >> 1;
+    let start = read_u16(subtable, 14 + seg_count * 2 + 2 + 2 * item)? as u32;
+    let last = read_u16(subtable, end_count + 2 * item)? as u32;
+    if codepoint < start || codepoint > last {
+        return Some(0);
+    }
+
+    let range_offset = 14 + seg_count * 6 + 2 + 2 * item;
+    match read_u16(subtable, range_offset)? as usize {
+        0 => {
+            let delta = read_u16(subtable, 14 + seg_count * 4 + 2 + 2 * item)?;
+            Some((codepoint as u16).wrapping_add(delta))
+        }
+        offset => read_u16(
+            subtable,
+            offset + (codepoint - start) as usize * 2 + range_offset,
+        ),
+    }
+}
+
+/// Maximum nesting of composite glyphs, stb walks composite glyphs recursively
+const MAX_COMPOSITE_DEPTH: usize = 16;
+
+/// Maximum number of vertices stb may allocate for a single glyph outline
+const MAX_GLYPH_VERTICES: usize = 1 << 20;
+
+/// Outline of a single glyph checked by `check_glyph`
+enum Outline {
+    /// Simple glyph or empty one, with the number of vertices stb allocates for it
+    Simple(usize),
+    /// Composite glyph made of the listed glyphs
+    Composite(Vec<usize>),
+}
+
+/// Glyph state while walking composite glyphs
+#[derive(Copy, Clone)]
+enum Visit {
+    Pending,
+    InProgress,
+    /// Glyph is checked, holds the number of vertices of the whole outline
+    Done(usize),
+}
+
+/// Checks that glyph locations returned by `location` are sorted and point inside `glyf`,
+/// every outline can be parsed without reading past the glyph data, and composite glyphs
+/// neither reference themselves nor nest too deep.
+fn check_glyphs<F>(glyf: &[u8], num_glyphs: usize, location: F) -> Option<()>
+where
+    F: Fn(usize) -> Option<usize>,
+{
+    let mut outlines = Vec::with_capacity(num_glyphs);
+    let mut start = location(0)?;
+    for glyph in 1..=num_glyphs {
+        let end = location(glyph)?;
+        if end < start || end > glyf.len() {
+            return None;
+        }
+
+        outlines.push(check_glyph(&glyf[start..end])?);
+        start = end;
+    }
+
+    let mut visits = vec![Visit::Pending; num_glyphs];
+    (0..num_glyphs).try_for_each(|glyph| {
+        count_vertices(&outlines, &mut visits, glyph, 0)
+            .filter(|&vertices| vertices <= MAX_GLYPH_VERTICES)
+            .map(|_| ())
+    })
+}
+
+/// Returns the number of vertices in the outline of `glyph` including its components,
+/// or `None` if components nest too deep or reference the glyph itself
+fn count_vertices(
+    outlines: &[Outline],
+    visits: &mut [Visit],
+    glyph: usize,
+    depth: usize,
+) -> Option<usize> {
+    if depth > MAX_COMPOSITE_DEPTH {
+        return None;
+    }
+
+    // stb skips components with an out of range glyph index
+    let components = match (outlines.get(glyph)?, visits[glyph]) {
+        (_, Visit::Done(vertices)) => return Some(vertices),
+        (_, Visit::InProgress) => return None,
+        (Outline::Simple(vertices), _) => {
+            visits[glyph] = Visit::Done(*vertices);
+            return Some(*vertices);
+        }
+        (Outline::Composite(components), _) => components,
+    };
+
+    visits[glyph] = Visit::InProgress;
+    let mut vertices = 0_usize;
+    for &component in components {
+        if component < outlines.len() {
+            let count = count_vertices(outlines, visits, component, depth + 1)?;
+            vertices = vertices.saturating_add(count);
+        }
+    }
+
+    visits[glyph] = Visit::Done(vertices);
+    Some(vertices)
+}
+
+/// Parses glyph header and outline the way `stbtt__GetGlyphShapeTT` does
+fn check_glyph(glyph: &[u8]) -> Option<Outline> {
+    if glyph.is_empty() {
+        return Some(Outline::Simple(0));
+    }
+
+    let num_contours = read_u16(glyph, 0)? as i16;
+    if glyph.len() < 10 {
+        return None;
+    }
+
+    // stb sizes glyph bitmaps from the bounding box in the glyph header
+    let coordinate = |offset: usize| read_u16(glyph, offset).map(|c| c as i16);
+    if coordinate(2)? > coordinate(6)? || coordinate(4)? > coordinate(8)? {
+        return None;
+    }
+
+    match num_contours {
+        0 => Some(Outline::Simple(0)),
+        n if n > 0 => check_simple_glyph(glyph, n as usize).map(Outline::Simple),
+        _ => check_composite_glyph(glyph).map(Outline::Composite),
+    }
+}
+
+/// Checks point data of a simple glyph and replays how stb converts points to vertices,
+/// returns the number of vertices stb allocates
+fn check_simple_glyph(glyph: &[u8], num_contours: usize) -> Option<usize> {
+    let end_points = glyph.get(10..10 + num_contours * 2)?;
+    let end_point = |contour: usize| read_u16(end_points, contour * 2).map(|p| p as usize);
+
+    let instructions = read_u16(glyph, 10 + num_contours * 2)? as usize;
+    let mut offset = 12 + num_contours * 2 + instructions;
+    let num_points = 1 + end_point(num_contours - 1)?;
+
+    // Flags with repeat counts, followed by x and y coordinates
+    let mut flags = Vec::with_capacity(num_points);
+    while flags.len() < num_points {
+        let flag = *glyph.get(offset)?;
+        offset += 1;
+
+        let mut repeat = 1;
+        if flag & 8 != 0 {
+            repeat += *glyph.get(offset)? as usize;
+            offset += 1;
+        }
+
+        let count = repeat.min(num_points - flags.len());
+        flags.resize(flags.len() + count, flag);
+    }
+
+    for (short, same) in [(2, 16), (4, 32)].iter() {
+        offset += flags
+            .iter()
+            .map(|flag| match (flag & short != 0, flag & same != 0) {
+                (true, _) => 1,
+                (false, true) => 0,
+                (false, false) => 2,
+            })
+            .sum::<usize>();
+    }
+    if offset > glyph.len() {
+        return None;
+    }
+
+    // stb reads the point after the start of a contour which begins off the curve, reads
+    // the end point of each contour it starts and writes vertices for every point into
+    // `num_points + 2 * num_contours` allocated vertices
+    let allocated = num_points + 2 * num_contours;
+    let mut vertices = 0;
+    let mut contour = 0;
+    let mut next_move = 0;
+    let mut was_off = false;
+    let mut start_off = false;
+    let close_shape = |was_off: bool, start_off: bool| match (start_off, was_off) {
+        (true, true) => 2,
+        _ => 1,
+    };
+
+    let mut i = 0;
+    while i < num_points {
+        let on_curve = flags[i] & 1 != 0;
+        if next_move == i {
+            if i != 0 {
+                vertices += close_shape(was_off, start_off);
+            }
+
+            start_off = !on_curve;
+            if start_off && *flags.get(i + 1)? & 1 != 0 {
+                i += 1;
+            }
+
+            vertices += 1;
+            was_off = false;
+            next_move = 1 + end_point(contour)?;
+            contour += 1;
+        } else if !on_curve {
+            if was_off {
+                vertices += 1;
+            }
+            was_off = true;
+        } else {
+            vertices += 1;
+            was_off = false;
+        }
+
+        if vertices > allocated {
+            return None;
+        }
+        i += 1;
+    }
+
+    vertices += close_shape(was_off, start_off);
+    if vertices > allocated {
+        return None;
+    }
+
+    Some(allocated)
+}
+
+/// Returns glyph indices of the components of a composite glyph, or `None` if the component
+/// records don't fit in the glyph data or use point matching, which stb asserts on
+fn check_composite_glyph(glyph: &[u8]) -> Option<Vec<usize>> {
+    let mut components = Vec::new();
+    let mut offset = 10;
+    loop {
+        let flags = read_u16(glyph, offset)?;
+        components.push(read_u16(glyph, offset + 2)? as usize);
+        offset += 4;
+
+        if flags & 2 == 0 {
+            return None;
+        }
+
+        offset += if flags & 1 != 0 { 4 } else { 2 };
+        offset += if flags & (1 << 3) != 0 {
+            2
+        } else if flags & (1 << 6) != 0 {
+            4
+        } else if flags & (1 << 7) != 0 {
+            8
+        } else {
+            0
+        };
+
+        if offset > glyph.len() {
+            return None;
+        }
+        if flags & (1 << 5) == 0 {
+            return Some(components);
+        }
+    }
+}
+
+/// Looks up the horizontal adjustment between `glyph1` and `glyph2` in pair positioning
+/// lookups of the `GPOS` table, the same way `stbtt__GetGlyphGPOSInfoAdvance` does.
+/// Data past the end of the table is treated as no adjustment.
+fn gpos_kern_advance(gpos: &[u8], glyph1: i32, glyph2: i32) -> i32 {
+    gpos_pair_adjustment(gpos, glyph1 as u16, glyph2 as u16).unwrap_or(0)
+}
+
+fn gpos_pair_adjustment(gpos: &[u8], glyph1: u16, glyph2: u16) -> Option<i32> {
+    // Version 1.0
+    if read_u16(gpos, 0)? != 1 || read_u16(gpos, 2)? != 0 {
+        return Some(0);
+    }
+
+    let lookup_list = gpos.get(read_u16(gpos, 8)? as usize..)?;
+    for i in 0..read_u16(lookup_list, 0)? as usize {
+        let lookup = lookup_list.get(read_u16(lookup_list, 2 + 2 * i)? as usize..)?;
+
+        // Pair adjustment positioning subtables
+        if read_u16(lookup, 0)? != 2 {
+            continue;
+        }
+
+        for j in 0..read_u16(lookup, 4)? as usize {
+            let table = lookup.get(read_u16(lookup, 6 + 2 * j)? as usize..)?;
+            let coverage = table.get(read_u16(table, 2)? as usize..)?;
+            let coverage_index = match coverage_index(coverage, glyph1) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            // stb only supports adjusting x advance of the first glyph
+            let format = read_u16(table, 0)?;
+            if (format == 1 || format == 2) && (read_u16(table, 4)?, read_u16(table, 6)?) != (4, 0)
+            {
+                return Some(0);
+            }
+
+            match format {
+                1 => {
+                    if coverage_index >= read_u16(table, 8)? as usize {
+                        return Some(0);
+                    }
+
+                    let pair_set =
+                        table.get(read_u16(table, 10 + 2 * coverage_index)? as usize..)?;
+                    let count = read_u16(pair_set, 0)? as usize;
+                    let index = binary_search(
+                        count,
+                        |m| read_u16(pair_set, 2 + 4 * m).map(|g| (g, g)),
+                        glyph2,
+                    )?;
+                    if let Some(m) = index {
+                        return Some(read_u16(pair_set, 4 + 4 * m)? as i16 as i32);
+                    }
+                }
+                2 => {
+                    let class1 = glyph_class(table.get(read_u16(table, 8)? as usize..)?, glyph1)?;
+                    let class2 = glyph_class(table.get(read_u16(table, 10)? as usize..)?, glyph2)?;
+                    let class1_count = read_u16(table, 12)? as usize;
+                    let class2_count = read_u16(table, 14)? as usize;
+                    if class1 >= class1_count || class2 >= class2_count {
+                        return Some(0);
+                    }
+
+                    let record = 16 + 2 * (class1 * class2_count) + 2 * class2;
+                    return Some(read_u16(table, record)? as i16 as i32);
+                }
+                _ => return Some(0),
+            }
+        }
+    }
+
+    Some(0)
+}
+
+/// Binary searches `count` sorted `(first, last)` glyph ranges returned by `range` for `glyph`,
+/// returns the index of the matching range or `None` if the range data is out of bounds
+fn binary_search<F>(count: usize, range: F, glyph: u16) -> Option<Option<usize>>
+where
+    F: Fn(usize) -> Option<(u16, u16)>,
+{
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let mid = (low + high) / 2;
+        let (first, last) = range(mid)?;
+        if glyph < first {
+            high = mid;
+        } else if glyph > last {
+            low = mid + 1;
+        } else {
+            return Some(Some(mid));
+        }
+    }
+
+    Some(None)
+}
+
+/// Returns the coverage index of `glyph`, or `None` if the glyph isn't covered
+fn coverage_index(coverage: &[u8], glyph: u16) -> Option<usize> {
+    let count = read_u16(coverage, 2)? as usize;
+    match read_u16(coverage, 0)? {
+        1 => binary_search(
+            count,
+            |m| read_u16(coverage, 4 + 2 * m).map(|g| (g, g)),
+            glyph,
+        )?,
+        2 => {
+            let range = |m: usize| {
+                Some((
+                    read_u16(coverage, 4 + 6 * m)?,
+                    read_u16(coverage, 6 + 6 * m)?,
+                ))
+            };
+            let m = binary_search(count, range, glyph)??;
+            let (start, _) = range(m)?;
+            Some(read_u16(coverage, 8 + 6 * m)? as usize + (glyph - start) as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the class of `glyph` in a class definition table, `None` if the table is invalid
+fn glyph_class(class_def: &[u8], glyph: u16) -> Option<usize> {
+    match read_u16(class_def, 0)? {
+        1 => {
+            let start = read_u16(class_def, 2)?;
+            let count = read_u16(class_def, 4)?;
+            if glyph >= start && ((glyph - start) as usize) < count as usize {
+                read_u16(class_def, 6 + 2 * (glyph - start) as usize).map(|c| c as usize)
+            } else {
+                Some(0)
+            }
+        }
+        2 => {
+            let count = read_u16(class_def, 2)? as usize;
+            let range = |m: usize| {
+                Some((
+                    read_u16(class_def, 4 + 6 * m)?,
+                    read_u16(class_def, 6 + 6 * m)?,
+                ))
+            };
+            match binary_search(count, range, glyph)? {
+                Some(m) => read_u16(class_def, 8 + 6 * m).map(|c| c as usize),
+                None => Some(0),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Maximum oversampling factor supported by stb (`STBTT_MAX_OVERSAMPLE`)
+const MAX_OVERSAMPLE: u32 = 8;
+
+/// Glyph packed into a `FontAtlas`
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PackedGlyph {
+    /// Left edge of the glyph rectangle in the atlas, in pixels
+    pub x0: u16,
+    /// Top edge of the glyph rectangle in the atlas, in pixels
+    pub y0: u16,
+    /// Right edge of the glyph rectangle in the atlas, in pixels
+    pub x1: u16,
+    /// Bottom edge of the glyph rectangle in the atlas, in pixels
+    pub y1: u16,
+    /// Texture coordinates of the glyph rectangle, normalized to 0..1
+    pub s0: f32,
+    pub t0: f32,
+    pub s1: f32,
+    pub t1: f32,
+    /// Offset from the glyph origin to the top left corner of the quad to draw
+    pub xoff: f32,
+    pub yoff: f32,
+    /// Offset from the glyph origin to the bottom right corner of the quad to draw
+    pub xoff2: f32,
+    pub yoff2: f32,
+    /// Horizontal advance in pixels
+    pub xadvance: f32,
+}
+
+/// Screen quad with texture coordinates to draw a packed glyph
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct AlignedQuad {
+    /// Top-left
+    pub x0: f32,
+    pub y0: f32,
+    pub s0: f32,
+    pub t0: f32,
+    /// Bottom-right
+    pub x1: f32,
+    pub y1: f32,
+    pub s1: f32,
+    pub t1: f32,
+}
+
+struct AtlasRange<'a> {
+    font: &'a FontInfo<'a>,
+    font_size: f32,
+    codepoints: Vec<char>,
+}
+
+/// Packs glyphs of one or more fonts into a single-channel texture atlas,
+/// using `stbtt_PackBegin` / `stbtt_PackFontRanges` API backed by `stb_rect_pack`.
+///
+/// ```no_run
+/// # use stb::truetype::{FontAtlasBuilder, FontInfo};
+/// # let font = FontInfo::new(std::fs::read("font.ttf").unwrap(), 0).unwrap();
+/// let mut builder = FontAtlasBuilder::new(512, 512);
+/// builder.oversampling(2, 2);
+/// let ascii = builder.add_range(&font, 24.0, ' '..='~');
+///
+/// let atlas = builder.build().expect("Atlas is too small");
+/// let glyph = atlas.glyph(ascii, 'A').unwrap();
+/// ```
+pub struct FontAtlasBuilder<'a> {
+    width: i32,
+    height: i32,
+    padding: i32,
+    h_oversample: u32,
+    v_oversample: u32,
+    skip_missing: bool,
+    ranges: Vec<AtlasRange<'a>>,
+}
+
+impl<'a> FontAtlasBuilder<'a> {
+    /// Creates a builder for an atlas of `width` x `height` pixels
+    pub fn new(width: i32, height: i32) -> Self {
+        FontAtlasBuilder {
+            width,
+            height,
+            padding: 1,
+            h_oversample: 1,
+            v_oversample: 1,
+            skip_missing: false,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Sets the amount of empty pixels between glyphs (1 by default).
+    /// Padding is needed to sample the atlas with bilinear filtering.
+    pub fn padding(&mut self, padding: i32) -> &mut Self {
+        self.padding = padding.max(0);
+        self
+    }
+
+    /// Sets horizontal and vertical oversampling (1 by default, no oversampling).
+    /// Oversampling increases the quality by allowing higher-quality subpixel positioning,
+    /// and is especially valuable at smaller text sizes. Values are clamped to `1..=8`.
+    pub fn oversampling(&mut self, h_oversample: u32, v_oversample: u32) -> &mut Self {
+        self.h_oversample = h_oversample.clamp(1, MAX_OVERSAMPLE);
+        self.v_oversample = v_oversample.clamp(1, MAX_OVERSAMPLE);
+        self
+    }
+
+    /// If enabled, codepoints missing in the font are not packed and can't be looked up in
+    /// the atlas. Otherwise missing codepoints are rendered as the font's "missing glyph".
+    pub fn skip_missing_codepoints(&mut self, skip: bool) -> &mut Self {
+        self.skip_missing = skip;
+        self
+    }
+
+    /// Adds `codepoints` of the `font` rendered at `font_size` pixels height to the atlas.
+    /// Use negative `font_size` to specify the EM size instead (see `STBTT_POINT_SIZE`).
+    /// Returns the range index to look up packed glyphs in `FontAtlas`.
+    pub fn add_range<I>(&mut self, font: &'a FontInfo<'a>, font_size: f32, codepoints: I) -> usize
+    where
+        I: IntoIterator<Item = char>,
+    {
+        self.ranges.push(AtlasRange {
+            font,
+            font_size,
+            codepoints: codepoints.into_iter().collect(),
+        });
+        self.ranges.len() - 1
+    }
+
+    /// Packs and renders all added ranges.
+    /// Returns `None` if the atlas size is invalid, the font scale for a range isn't positive,
+    /// a glyph bitmap is larger than the atlas, or the glyphs don't fit in the atlas.
+    pub fn build(&self) -> Option<FontAtlas> {
+        if self.width <= 0 || self.height <= 0 {
+            return None;
+        }
+
+        // stb addresses atlas pixels with `int` offsets
+        let size = (self.width as usize)
+            .checked_mul(self.height as usize)
+            .filter(|&size| size <= raw::c_int::MAX as usize)?;
+
+        let mut pixels = vec![0_u8; size];
+
+        let mut ranges = self
+            .ranges
+            .iter()
+            .map(|range| {
+                let codepoints = range
+                    .codepoints
+                    .iter()
+                    .cloned()
+                    .filter(|c| !self.skip_missing || range.font.find_glyph_index(*c).is_some())
+                    .collect::<Vec<_>>();
+                PackedRange::new(codepoints)
+            })
+            .collect::<Vec<_>>();
+
+        // stb doesn't check glyph bitmap boxes before packing them
+        let valid = self
+            .ranges
+            .iter()
+            .zip(ranges.iter())
+            .all(|(range, packed)| {
+                packed
+                    .codepoints
+                    .keys()
+                    .all(|codepoint| self.is_valid_glyph_rect(range, *codepoint))
+            });
+        if !valid {
+            return None;
+        }
+
+        // `stbtt_pack_range` points to codepoint and char data owned by `ranges`,
+        // which must not be reallocated until packing is done.
+        let mut sys_ranges = self
+            .ranges
+            .iter()
+            .zip(ranges.iter_mut())
+            .map(|(range, packed)| sys::stbtt_pack_range {
+                font_size: range.font_size,
+                first_unicode_codepoint_in_range: 0,
+                array_of_unicode_codepoints: packed.sys_codepoints.as_mut_ptr(),
+                num_chars: packed.sys_codepoints.len() as raw::c_int,
+                chardata_for_range: packed.chars.as_mut_ptr(),
+                h_oversample: 0,
+                v_oversample: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let mut context = MaybeUninit::<sys::stbtt_pack_context>::uninit();
+        let ret = unsafe {
+            sys::stbtt_PackBegin(
+                context.as_mut_ptr(),
+                pixels.as_mut_ptr(),
+                self.width,
+                self.height,
+                0,
+                self.padding,
+                ptr::null_mut(),
+            )
+        };
+        if ret == 0 {
+            return None;
+        }
+
+        let mut context = unsafe { context.assume_init() };
+        unsafe {
+            sys::stbtt_PackSetOversampling(&mut context, self.h_oversample, self.v_oversample)
+        };
+
+        let rect = sys::stbrp_rect {
+            id: 0,
+            w: 0,
+            h: 0,
+            x: 0,
+            y: 0,
+            was_packed: 0,
+        };
+        let mut rects = vec![rect; ranges.iter().map(|r| r.chars.len()).sum()];
+
+        // Gather rects of all ranges first, so glyphs of all fonts are packed together
+        let mut offsets = Vec::with_capacity(sys_ranges.len());
+        let mut count = 0;
+        for (range, sys_range) in self.ranges.iter().zip(sys_ranges.iter_mut()) {
+            offsets.push(count);
+            count += unsafe {
+                sys::stbtt_PackFontRangesGatherRects(
+                    &mut context,
+                    &range.font.info,
+                    sys_range,
+                    1,
+                    rects[count..].as_mut_ptr(),
+                )
+            } as usize;
+        }
+
+        unsafe { sys::stbtt_PackFontRangesPackRects(&mut context, rects.as_mut_ptr(), count as _) };
+
+        let packed = rects[..count].iter().all(|r| r.was_packed != 0);
+        if packed {
+            for ((range, sys_range), offset) in
+                self.ranges.iter().zip(sys_ranges.iter_mut()).zip(offsets)
+            {
+                unsafe {
+                    sys::stbtt_PackFontRangesRenderIntoRects(
+                        &mut context,
+                        &range.font.info,
+                        sys_range,
+                        1,
+                        rects[offset..].as_mut_ptr(),
+                    )
+                };
+            }
+        }
+
+        unsafe { sys::stbtt_PackEnd(&mut context) };
+
+        if !packed {
+            return None;
+        }
+
+        Some(FontAtlas {
+            width: self.width,
+            height: self.height,
+            pixels,
+            ranges,
+        })
+    }
+
+    /// Returns `true` if the rect stb gathers for the codepoint isn't inverted
+    /// and fits in the atlas, the same way as `stbtt_PackFontRangesGatherRects` computes it
+    fn is_valid_glyph_rect(&self, range: &AtlasRange<'_>, codepoint: char) -> bool {
+        let font = range.font;
+        let scale = if range.font_size > 0.0 {
+            font.scale_for_pixel_height(range.font_size)
+        } else {
+            font.scale_for_mapping_em_to_pixels(-range.font_size)
+        };
+
+        let glyph = font.find_glyph_index(codepoint).unwrap_or(0);
+        let scale_x = scale * self.h_oversample as f32;
+        let scale_y = scale * self.v_oversample as f32;
+        let width = self.width as i64;
+        let height = self.height as i64;
+        matches!(
+            font.bitmap_size(glyph, scale_x, scale_y, 0.0, 0.0, 0),
+            Some((w, h)) if (0..=width).contains(&w) && (0..=height).contains(&h)
+        )
+    }
+}
+
+struct PackedRange {
+    codepoints: HashMap<char, usize>,
+    sys_codepoints: Vec<raw::c_int>,
+    chars: Vec<sys::stbtt_packedchar>,
+}
+
+impl PackedRange {
+    fn new(codepoints: Vec<char>) -> Self {
+        let packed_char = sys::stbtt_packedchar {
+            x0: 0,
+            y0: 0,
+            x1: 0,
+            y1: 0,
+            xoff: 0.0,
+            yoff: 0.0,
+            xadvance: 0.0,
+            xoff2: 0.0,
+            yoff2: 0.0,
+        };
+
+        PackedRange {
+            chars: vec![packed_char; codepoints.len()],
+            sys_codepoints: codepoints.iter().map(|c| *c as raw::c_int).collect(),
+            codepoints: codepoints
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (c, i))
+                .collect(),
+        }
+    }
+}
+
+/// Single-channel texture atlas with packed glyphs, see `FontAtlasBuilder`
+pub struct FontAtlas {
+    width: i32,
+    height: i32,
+    pixels: Vec<u8>,
+    ranges: Vec<PackedRange>,
+}
+
+impl FontAtlas {
+    /// Atlas width in pixels
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Atlas height in pixels
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Returns atlas pixels (one byte per pixel, `width * height` bytes)
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Consumes the atlas into its pixels
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    /// Returns packed glyph of a `codepoint` from the range returned by `FontAtlasBuilder::add_range`
+    pub fn glyph(&self, range: usize, codepoint: char) -> Option<PackedGlyph> {
+        let range = self.ranges.get(range)?;
+        let index = *range.codepoints.get(&codepoint)?;
+        Some(self.packed_glyph(&range.chars[index]))
+    }
+
+    /// Returns all packed glyphs of the range returned by `FontAtlasBuilder::add_range`
+    pub fn glyphs(&self, range: usize) -> impl Iterator<Item = (char, PackedGlyph)> + '_ {
+        self.ranges.get(range).into_iter().flat_map(move |range| {
+            range
+                .codepoints
+                .iter()
+                .map(move |(c, i)| (*c, self.packed_glyph(&range.chars[*i])))
+        })
+    }
+
+    /// Computes the quad to draw `codepoint` at the current position (`xpos`, `ypos`) and
+    /// advances `xpos` to the next character position.
+    /// Set `align_to_integer` to snap the quad to integer pixels (only for non-oversampled ranges).
+    pub fn quad(
+        &self,
+        range: usize,
+        codepoint: char,
+        xpos: &mut f32,
+        ypos: &mut f32,
+        align_to_integer: bool,
+    ) -> Option<AlignedQuad> {
+        let range = self.ranges.get(range)?;
+        let index = *range.codepoints.get(&codepoint)?;
+
+        let mut q = MaybeUninit::<sys::stbtt_aligned_quad>::uninit();
+        let q = unsafe {
+            sys::stbtt_GetPackedQuad(
+                range.chars.as_ptr(),
+                self.width,
+                self.height,
+                index as raw::c_int,
+                xpos,
+                ypos,
+                q.as_mut_ptr(),
+                align_to_integer as raw::c_int,
+            );
+            q.assume_init()
+        };
+
+        Some(AlignedQuad {
+            x0: q.x0,
+            y0: q.y0,
+            s0: q.s0,
+            t0: q.t0,
+            x1: q.x1,
+            y1: q.y1,
+            s1: q.s1,
+            t1: q.t1,
+        })
+    }
+
+    fn packed_glyph(&self, c: &sys::stbtt_packedchar) -> PackedGlyph {
+        let width = self.width as f32;
+        let height = self.height as f32;
+
+        PackedGlyph {
+            x0: c.x0,
+            y0: c.y0,
+            x1: c.x1,
+            y1: c.y1,
+            s0: c.x0 as f32 / width,
+            t0: c.y0 as f32 / height,
+            s1: c.x1 as f32 / width,
+            t1: c.y1 as f32 / height,
+            xoff: c.xoff,
+            yoff: c.yoff,
+            xoff2: c.xoff2,
+            yoff2: c.yoff2,
+            xadvance: c.xadvance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixture_path(file: &str) -> PathBuf {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut path = PathBuf::from(root.parent().unwrap());
+
+        path.push("tests/fixtures");
+        path.push(file);
+
+        path
+    }
+
+    fn load_font() -> FontInfo<'static> {
+        let data = fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+        FontInfo::new(data, 0).expect("Failed to parse font")
+    }
+
+    #[test]
+    fn number_of_fonts() {
+        let data = fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+        assert_eq!(stbtt_get_number_of_fonts(&data), Some(1));
+        assert_eq!(stbtt_get_font_offset_for_index(&data, 0), Some(0));
+        assert_eq!(stbtt_get_font_offset_for_index(&data, 1), None);
+    }
+
+    #[test]
+    fn init_from_borrowed_data() {
+        let data = fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+        let font = FontInfo::new(data.as_slice(), 0).expect("Failed to parse font");
+
+        assert!(font.num_glyphs() > 0);
+        assert_eq!(font.data().len(), data.len());
+    }
+
+    #[test]
+    fn init_invalid() {
+        assert!(FontInfo::new(&[0_u8; 0][..], 0).is_none());
+        assert!(FontInfo::new(&[0_u8; 64][..], 0).is_none());
+    }
+
+    #[test]
+    fn init_out_of_range() {
+        let data = fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+
+        // Tables past the end of the data
+        assert!(FontInfo::new(&data[..data.len() / 2], 0).is_none());
+
+        // Directory larger than the data
+        let mut header = data[..64].to_vec();
+        header[4..6].copy_from_slice(&1000_u16.to_be_bytes());
+        assert!(FontInfo::new(header, 0).is_none());
+
+        // Table record pointing outside of the data
+        let mut corrupted = data.clone();
+        let num_tables = u16::from_be_bytes([data[4], data[5]]) as usize;
+        for record in (0..num_tables).map(|i| 12 + i * 16) {
+            if &data[record..record + 4] == b"glyf" {
+                corrupted[record + 8..record + 12].copy_from_slice(&u32::MAX.to_be_bytes());
+            }
+        }
+        assert!(FontInfo::new(corrupted, 0).is_none());
+
+        // Collection claiming more fonts than it has offsets for
+        let mut collection = b"ttcf\0\x01\0\0".to_vec();
+        collection.extend_from_slice(&100_u32.to_be_bytes());
+        assert_eq!(stbtt_get_number_of_fonts(&collection), Some(100));
+        assert_eq!(stbtt_get_font_offset_for_index(&collection, 50), None);
+        assert_eq!(stbtt_get_font_offset_for_index(&collection, -1), None);
+        assert!(FontInfo::new(collection, 0).is_none());
+    }
+
+    /// Returns the location of a glyph in the font data
+    fn glyph_range(data: &[u8], glyph: usize) -> Range<usize> {
+        let head = find_table(data, 0, b"head").unwrap();
+        let loca = find_table(data, 0, b"loca").unwrap();
+        let glyf = find_table_range(data, 0, b"glyf").unwrap();
+
+        let location = |glyph: usize| match read_u16(head, 50).unwrap() {
+            0 => read_u16(loca, glyph * 2).unwrap() as usize * 2,
+            _ => read_u32(loca, glyph * 4).unwrap() as usize,
+        };
+        glyf.start + location(glyph)..glyf.start + location(glyph + 1)
+    }
+
+    #[test]
+    fn init_malformed() {
+        let data = fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+        let font = FontInfo::new(data.as_slice(), 0).expect("Failed to parse font");
+        let a = glyph_range(&data, font.find_glyph_index('A').unwrap() as usize);
+
+        // Instructions running past the end of the glyph
+        let mut corrupted = data.clone();
+        let contours = read_u16(&data, a.start).unwrap() as usize;
+        let instructions = a.start + 10 + contours * 2;
+        corrupted[instructions..instructions + 2].copy_from_slice(&u16::MAX.to_be_bytes());
+        assert!(FontInfo::new(corrupted, 0).is_none());
+
+        // Inverted bounding box
+        let mut corrupted = data.clone();
+        corrupted[a.start + 2..a.start + 4].copy_from_slice(&i16::MAX.to_be_bytes());
+        assert!(FontInfo::new(corrupted, 0).is_none());
+
+        // Composite glyph made of itself
+        let glyph = font.find_glyph_index('\u{c1}').unwrap();
+        let composite = glyph_range(&data, glyph as usize);
+        assert!((read_u16(&data, composite.start).unwrap() as i16) < 0);
+        let mut corrupted = data.clone();
+        let component = composite.start + 12;
+        corrupted[component..component + 2].copy_from_slice(&(glyph as u16).to_be_bytes());
+        assert!(FontInfo::new(corrupted, 0).is_none());
+
+        // cmap pointing to glyphs beyond the number of glyphs
+        let mut corrupted = data.clone();
+        let maxp = find_table_range(&data, 0, b"maxp").unwrap();
+        corrupted[maxp.start + 4..maxp.start + 6].copy_from_slice(&10_u16.to_be_bytes());
+        assert!(FontInfo::new(corrupted, 0).is_none());
+
+        // No TrueType outlines, stb would parse the font as CFF
+        let mut corrupted = data.clone();
+        let num_tables = read_u16(&data, 4).unwrap() as usize;
+        for record in (0..num_tables).map(|i| 12 + i * 16) {
+            if &data[record..record + 4] == b"glyf" {
+                corrupted[record..record + 4].copy_from_slice(b"xxxx");
+            }
+        }
+        assert!(FontInfo::new(corrupted, 0).is_none());
+    }
+
+    #[test]
+    fn init_unchecked() {
+        let data = fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+        let font =
+            unsafe { FontInfo::new_unchecked(data.as_slice(), 0) }.expect("Failed to parse font");
+
+        let checked = load_font();
+        let a = font.find_glyph_index('A').unwrap();
+        assert_eq!(checked.find_glyph_index('A'), Some(a));
+        assert_eq!(font.glyph_h_metrics(a), checked.glyph_h_metrics(a));
+    }
+
+    #[test]
+    fn glyph_lookup() {
+        let font = load_font();
+
+        let glyph = font.find_glyph_index('A').expect("Failed to find glyph");
+        assert!(glyph > 0);
+        assert!(!font.is_glyph_empty(glyph));
+        assert!(font.is_glyph_empty(font.num_glyphs()));
+
+        assert!(font.find_glyph_index('\u{10FFFF}').is_none());
+    }
+
+    #[test]
+    fn metrics() {
+        let font = load_font();
+
+        let a = font.codepoint_h_metrics('A');
+        let i = font.codepoint_h_metrics('i');
+        assert!(a.advance_width > 0);
+        // Monospace font
+        assert_eq!(a.advance_width, i.advance_width);
+
+        let glyph = font.find_glyph_index('A').unwrap();
+        assert_eq!(font.glyph_h_metrics(glyph), Some(a));
+        assert_eq!(font.glyph_h_metrics(-1), None);
+
+        let v = font.v_metrics();
+        assert!(v.ascent > 0);
+        assert!(v.descent < 0);
+
+        let bbox = font.glyph_box(glyph).expect("Failed to get glyph box");
+        assert!(bbox.width() > 0 && bbox.height() > 0);
+        assert!(font.bounding_box().width() >= bbox.width());
+
+        let scale = font.scale_for_pixel_height(32.0);
+        assert!((scale * (v.ascent - v.descent) as f32 - 32.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn kerning() {
+        let font = load_font();
+
+        // Monospace fonts have no kerning
+        assert_eq!(font.codepoint_kern_advance('A', 'V'), 0);
+
+        let a = font.find_glyph_index('A').unwrap();
+        let v = font.find_glyph_index('V').unwrap();
+        assert_eq!(font.glyph_kern_advance(a, v), Some(0));
+        assert_eq!(font.glyph_kern_advance(a, font.num_glyphs()), None);
+    }
+
+    #[test]
+    fn glyph_bitmap() {
+        let font = load_font();
+        let scale = font.scale_for_pixel_height(24.0);
+        let glyph = font.find_glyph_index('A').unwrap();
+
+        let bbox = font
+            .glyph_bitmap_box(glyph, scale, scale)
+            .expect("Failed to get bitmap box");
+        let bitmap = font
+            .glyph_bitmap(glyph, scale, scale)
+            .expect("Failed to render glyph");
+
+        assert_eq!(bitmap.width, bbox.width());
+        assert_eq!(bitmap.height, bbox.height());
+        assert_eq!(bitmap.xoff, bbox.x0);
+        assert_eq!(bitmap.yoff, bbox.y0);
+        assert_eq!(bitmap.data.len(), bitmap.size());
+        assert!(bitmap.data.contains(&255));
+        assert_eq!(bitmap.pixel(bitmap.width, 0), None);
+
+        assert!(font.glyph_bitmap(-1, scale, scale).is_none());
+    }
+
+    #[test]
+    fn glyph_bitmap_empty() {
+        let font = load_font();
+        let scale = font.scale_for_pixel_height(24.0);
+        let space = font.find_glyph_index(' ').unwrap();
+
+        let bitmap = font
+            .glyph_bitmap(space, scale, scale)
+            .expect("Failed to render glyph");
+        assert!(bitmap.is_empty());
+
+        let sdf = font
+            .glyph_sdf(space, scale, 4, 128, 32.0)
+            .expect("Failed to render SDF");
+        assert!(sdf.is_empty());
+    }
+
+    #[test]
+    fn glyph_sdf() {
+        let font = load_font();
+        let scale = font.scale_for_pixel_height(24.0);
+        let glyph = font.find_glyph_index('O').unwrap();
+        let padding = 4;
+
+        let bitmap = font.glyph_bitmap(glyph, scale, scale).unwrap();
+        let sdf = font
+            .glyph_sdf(glyph, scale, padding, 128, 32.0)
+            .expect("Failed to render SDF");
+
+        assert_eq!(sdf.width, bitmap.width + padding * 2);
+        assert_eq!(sdf.height, bitmap.height + padding * 2);
+        assert_eq!(sdf.xoff, bitmap.xoff - padding);
+        assert_eq!(sdf.yoff, bitmap.yoff - padding);
+        assert_eq!(sdf.data.len(), sdf.size());
+
+        // Corners are outside of the glyph
+        assert!(sdf.pixel(0, 0).unwrap() < 128);
+
+        assert!(font.glyph_sdf(glyph, 0.0, padding, 128, 32.0).is_none());
+    }
+
+    #[test]
+    fn glyph_bitmap_invalid_scale() {
+        let font = load_font();
+        let glyph = font.find_glyph_index('A').unwrap();
+
+        assert!(font.glyph_bitmap(glyph, -1.0, 1.0).is_none());
+        assert!(font.glyph_bitmap(glyph, 0.01, -0.0001).is_none());
+        assert!(font.glyph_bitmap(glyph, 0.0, 0.0).is_none());
+        assert!(font.glyph_bitmap(glyph, 1.0e6, 1.0e6).is_none());
+        assert!(font.glyph_bitmap(glyph, f32::INFINITY, 1.0).is_none());
+        assert!(font.glyph_bitmap(glyph, f32::NAN, 1.0).is_none());
+        assert!(font.glyph_sdf(glyph, -0.1, 0, 128, 32.0).is_none());
+        assert!(font.glyph_sdf(glyph, 0.1, -100, 128, 32.0).is_none());
+        assert!(font.glyph_sdf(glyph, 0.1, i32::MAX, 128, 32.0).is_none());
+    }
+
+    #[test]
+    fn glyph_bitmap_invalid_size() {
+        let bitmap = GlyphBitmap {
+            width: -2,
+            height: 3,
+            ..GlyphBitmap::default()
+        };
+        assert_eq!(bitmap.size(), 0);
+    }
+
+    #[test]
+    fn font_atlas() {
+        let font = load_font();
+
+        let mut builder = FontAtlasBuilder::new(256, 256);
+        builder.oversampling(2, 2);
+        let ascii = builder.add_range(&font, 16.0, ' '..='~');
+        let digits = builder.add_range(&font, 32.0, '0'..='9');
+
+        let atlas = builder.build().expect("Failed to build atlas");
+        assert_eq!(atlas.pixels().len(), 256 * 256);
+        assert_eq!(atlas.glyphs(ascii).count(), 95);
+        assert_eq!(atlas.glyphs(digits).count(), 10);
+
+        let small = atlas.glyph(ascii, '0').expect("Failed to find glyph");
+        let big = atlas.glyph(digits, '0').expect("Failed to find glyph");
+        assert!(big.x1 - big.x0 > small.x1 - small.x0);
+        assert!(small.s0 < small.s1 && small.t0 < small.t1);
+        assert!(small.s1 <= 1.0 && small.t1 <= 1.0);
+
+        // Glyph rectangle contains rendered pixels
+        let has_pixels = (small.y0..small.y1).any(|y| {
+            (small.x0..small.x1).any(|x| atlas.pixels()[y as usize * 256 + x as usize] != 0)
+        });
+        assert!(has_pixels);
+
+        assert!(atlas.glyph(digits, 'A').is_none());
+        assert!(atlas.glyph(2, '0').is_none());
+        assert_eq!(atlas.glyphs(2).count(), 0);
+    }
+
+    #[test]
+    fn font_atlas_quad() {
+        let font = load_font();
+
+        let mut builder = FontAtlasBuilder::new(256, 256);
+        let range = builder.add_range(&font, 16.0, 'A'..='Z');
+        let atlas = builder.build().expect("Failed to build atlas");
+
+        let glyph = atlas.glyph(range, 'A').unwrap();
+        let (mut x, mut y) = (10.0, 20.0);
+        let quad = atlas
+            .quad(range, 'A', &mut x, &mut y, true)
+            .expect("Failed to get quad");
+
+        assert_eq!(x, 10.0 + glyph.xadvance);
+        assert_eq!(y, 20.0);
+        assert_eq!(quad.s0, glyph.s0);
+        assert_eq!(quad.t1, glyph.t1);
+        assert!(quad.x0 < quad.x1 && quad.y0 < quad.y1);
+    }
+
+    #[test]
+    fn font_atlas_too_small() {
+        let font = load_font();
+
+        let mut builder = FontAtlasBuilder::new(16, 16);
+        builder.add_range(&font, 32.0, 'A'..='Z');
+        assert!(builder.build().is_none());
+    }
+
+    #[test]
+    fn font_atlas_invalid_size() {
+        assert!(FontAtlasBuilder::new(0, 16).build().is_none());
+        assert!(FontAtlasBuilder::new(16, -1).build().is_none());
+        assert!(FontAtlasBuilder::new(i32::MAX, i32::MAX).build().is_none());
+        assert!(FontAtlasBuilder::new(65536, 65536).build().is_none());
+    }
+
+    #[test]
+    fn font_atlas_invalid_font_size() {
+        let font = load_font();
+
+        for font_size in [1.0e30, -1.0e30, f32::NAN] {
+            let mut builder = FontAtlasBuilder::new(256, 256);
+            builder.add_range(&font, font_size, 'A'..='Z');
+            assert!(builder.build().is_none());
+        }
+
+        // Negative ascent makes scale for pixel height negative
+        let mut data =
+            fs::read(fixture_path("DejaVuSansMono.ttf")).expect("Failed to read font file");
+        let hhea = find_table_range(&data, 0, b"hhea").unwrap();
+        data[hhea.start + 4..hhea.start + 6].copy_from_slice(&(-4096_i16).to_be_bytes());
+        let font = FontInfo::new(data, 0).expect("Failed to parse font");
+        assert!(font.scale_for_pixel_height(12.0) < 0.0);
+
+        let mut builder = FontAtlasBuilder::new(256, 256);
+        builder.add_range(&font, 12.0, 'A'..='Z');
+        assert!(builder.build().is_none());
+    }
+
+    #[test]
+    fn font_atlas_skip_missing() {
+        let font = load_font();
+
+        let mut builder = FontAtlasBuilder::new(64, 64);
+        builder.skip_missing_codepoints(true);
+        let range = builder.add_range(&font, 16.0, vec!['A', '\u{10FFFF}']);
+        let atlas = builder.build().expect("Failed to build atlas");
+
+        assert!(atlas.glyph(range, 'A').is_some());
+        assert!(atlas.glyph(range, '\u{10FFFF}').is_none());
+    }
+}